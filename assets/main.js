@@ -26,4 +26,19 @@ function createSortable() {
 			}
 		});
 	}
-}
\ No newline at end of file
+}
+// Uploaded option lists are read into the options textarea, where the server previews them
+document.addEventListener("change", function (event) {
+	if (event.target.id !== "optionsFileInput") {
+		return;
+	}
+
+	var file = event.target.files[0];
+	var optionsText = document.getElementById("optionsTextInput");
+	if (file && optionsText) {
+		file.text().then(function (text) {
+			optionsText.value = text;
+			optionsText.dispatchEvent(new Event("change", { bubbles: true }));
+		});
+	}
+});
@@ -0,0 +1,141 @@
+use anyhow::bail;
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::views::{Ballot, CreateRoomRequest, ErrorBody, RoomView, SessionView, TallyView};
+
+const SESSION_COOKIE_NAME: &str = "whatdo_session";
+
+/// Talks to a What Do? server's JSON API as one participant
+pub struct Client {
+    agent: ureq::Agent,
+    server: String,
+    /// The server identifies participants by their session cookie, which it hands out on the first
+    /// request. Its value is a secret token, not the participant's id.
+    session: Option<String>,
+    /// The participant id the session goes by in rooms, once the server has been asked
+    participant: Option<Uuid>,
+}
+
+impl Client {
+    pub fn new(server: &str, session: Option<String>) -> Self {
+        Self {
+            agent: ureq::Agent::new(),
+            server: server.trim_end_matches('/').to_string(),
+            session,
+            participant: None,
+        }
+    }
+
+    pub fn session(&self) -> Option<&str> {
+        self.session.as_deref()
+    }
+
+    /// The id other participants see this client as
+    pub fn participant(&mut self) -> anyhow::Result<Uuid> {
+        if let Some(participant) = self.participant {
+            return Ok(participant);
+        }
+        let session: SessionView = self.send("GET", "/api/session", None)?;
+        self.participant = Some(session.participant);
+        Ok(session.participant)
+    }
+
+    pub fn create_room(&mut self, request: &CreateRoomRequest) -> anyhow::Result<RoomView> {
+        self.send("POST", "/api/rooms", Some(json!(request)))
+    }
+
+    pub fn join_room(&mut self, code: &str, nickname: &str) -> anyhow::Result<RoomView> {
+        let path = format!("/api/rooms/{}/participants", code);
+        self.send("POST", &path, Some(json!({ "nickname": nickname })))
+    }
+
+    pub fn get_room(&mut self, code: &str) -> anyhow::Result<RoomView> {
+        self.send("GET", &format!("/api/rooms/{}", code), None)
+    }
+
+    pub fn add_option(&mut self, code: &str, text: &str) -> anyhow::Result<RoomView> {
+        let path = format!("/api/rooms/{}/options", code);
+        self.send("POST", &path, Some(json!({ "text": text })))
+    }
+
+    pub fn veto(&mut self, code: &str, option: Uuid, reason: &str) -> anyhow::Result<RoomView> {
+        let path = format!("/api/rooms/{}/options/{}/veto", code, option);
+        self.send("POST", &path, Some(json!({ "reason": reason })))
+    }
+
+    pub fn undo_veto(
+        &mut self,
+        code: &str,
+        option: Uuid,
+        vetoer: Uuid,
+    ) -> anyhow::Result<RoomView> {
+        let path = format!("/api/rooms/{}/options/{}/vetoes/{}", code, option, vetoer);
+        self.send("DELETE", &path, None)
+    }
+
+    pub fn set_ready(&mut self, code: &str, ready: bool) -> anyhow::Result<RoomView> {
+        let path = format!("/api/rooms/{}/ready", code);
+        self.send("POST", &path, Some(json!({ "ready": ready })))
+    }
+
+    pub fn finish_vetoing(&mut self, code: &str) -> anyhow::Result<RoomView> {
+        self.send("POST", &format!("/api/rooms/{}/finish-vetoing", code), None)
+    }
+
+    pub fn submit_ballot(&mut self, code: &str, ballot: &Ballot) -> anyhow::Result<RoomView> {
+        let path = format!("/api/rooms/{}/ballots", code);
+        self.send("POST", &path, Some(json!(ballot)))
+    }
+
+    pub fn get_tally(&mut self, code: &str) -> anyhow::Result<TallyView> {
+        self.send("GET", &format!("/api/rooms/{}/tally", code), None)
+    }
+
+    fn send<T: DeserializeOwned>(
+        &mut self,
+        method: &str,
+        path: &str,
+        body: Option<Value>,
+    ) -> anyhow::Result<T> {
+        let mut request = self
+            .agent
+            .request(method, &format!("{}{}", self.server, path));
+        if let Some(session) = &self.session {
+            request = request.set("Cookie", &format!("{}={}", SESSION_COOKIE_NAME, session));
+        }
+        let result = match body {
+            Some(body) => request.send_json(body),
+            None => request.call(),
+        };
+
+        let response = match result {
+            Ok(response) => response,
+            Err(ureq::Error::Status(status, response)) => {
+                let message = response.into_json::<ErrorBody>().map_or_else(
+                    |_| format!("The server responded with status {}", status),
+                    |body| body.error,
+                );
+                bail!(message);
+            }
+            Err(e) => bail!("Couldn't reach {}: {}", self.server, e),
+        };
+        // The server starts a new session if it didn't recognise the one given
+        if let Some(session) = response
+            .all("set-cookie")
+            .into_iter()
+            .find_map(parse_session_cookie)
+        {
+            self.session = Some(session);
+            self.participant = None;
+        }
+
+        Ok(response.into_json()?)
+    }
+}
+
+fn parse_session_cookie(set_cookie: &str) -> Option<String> {
+    let (name, value) = set_cookie.split(';').next()?.trim().split_once('=')?;
+    (name == SESSION_COOKIE_NAME).then(|| value.to_string())
+}
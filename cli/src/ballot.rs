@@ -0,0 +1,197 @@
+use std::{
+    collections::HashSet,
+    io::{self, BufRead, Write},
+};
+
+use anyhow::bail;
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    execute, queue,
+    style::{Attribute, Print, SetAttribute},
+    terminal::{self, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use uuid::Uuid;
+
+use crate::{
+    api::Client,
+    views::{Ballot, OptionView, RoomView, VotingStage},
+};
+
+/// Keeps the terminal in raw mode on its alternate screen for as long as it lives, so the ranking
+/// screen can react to single key presses and leaves the scrollback alone
+struct RawTerminal;
+
+impl RawTerminal {
+    fn enter() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        execute!(io::stdout(), EnterAlternateScreen, cursor::Hide)?;
+        Ok(Self)
+    }
+}
+
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), cursor::Show, LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// Asks for a ballot of whichever kind the room is voting with and submits it. Returns `false` if
+/// the user backed out instead.
+pub fn vote(client: &mut Client, room: &RoomView) -> anyhow::Result<bool> {
+    let options = room.remaining_options();
+    if options.is_empty() {
+        bail!("Every option was vetoed, so there's nothing to vote on");
+    }
+
+    let ballot = match room.voting_stage {
+        VotingStage::Ranking => rank(options)?.map(|ranking| Ballot::Ranked { ranking }),
+        VotingStage::Approving => Some(Ballot::Approval {
+            approved: approve(&options)?,
+        }),
+        VotingStage::Scoring => Some(Ballot::Score {
+            scores: score(&options, room.max_score)?,
+        }),
+        VotingStage::Vetoing | VotingStage::Closed => bail!("The room isn't taking ballots"),
+    };
+
+    match ballot {
+        Some(ballot) => {
+            client.submit_ballot(&room.code, &ballot)?;
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+/// Lets the user put the options in order, favourite first. Returns `None` if they cancel.
+fn rank(mut order: Vec<&OptionView>) -> anyhow::Result<Option<Vec<Uuid>>> {
+    let _raw_terminal = RawTerminal::enter()?;
+    let mut selected = 0;
+    let mut holding = false;
+    loop {
+        draw_ranking(&order, selected, holding)?;
+        let Event::Key(KeyEvent {
+            code,
+            modifiers,
+            kind: KeyEventKind::Press,
+            ..
+        }) = event::read()?
+        else {
+            continue;
+        };
+
+        match code {
+            KeyCode::Up | KeyCode::Char('k') if selected > 0 => {
+                if holding {
+                    order.swap(selected, selected - 1);
+                }
+                selected -= 1;
+            }
+            KeyCode::Down | KeyCode::Char('j') if selected + 1 < order.len() => {
+                if holding {
+                    order.swap(selected, selected + 1);
+                }
+                selected += 1;
+            }
+            KeyCode::Char(' ') => holding = !holding,
+            KeyCode::Enter => return Ok(Some(order.iter().map(|option| option.id).collect())),
+            KeyCode::Esc | KeyCode::Char('q') => return Ok(None),
+            KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => return Ok(None),
+            _ => (),
+        }
+    }
+}
+
+fn draw_ranking(order: &[&OptionView], selected: usize, holding: bool) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    // Raw mode doesn't turn \n into a new line at the start of the row, so lines end in \r\n
+    queue!(
+        stdout,
+        terminal::Clear(ClearType::All),
+        cursor::MoveTo(0, 0),
+        Print("Rank the options, favourite first\r\n"),
+        Print("Up/down (or k/j) to move, space to pick up or put down, enter to submit, esc to cancel\r\n\r\n"),
+    )?;
+    for (index, option) in order.iter().enumerate() {
+        let marker = match (index == selected, holding) {
+            (true, true) => "=>",
+            (true, false) => "> ",
+            (false, _) => "  ",
+        };
+        if index == selected {
+            queue!(stdout, SetAttribute(Attribute::Reverse))?;
+        }
+        queue!(
+            stdout,
+            Print(format!("{} {:>2}. {}", marker, index + 1, option.text)),
+            SetAttribute(Attribute::Reset),
+            Print("\r\n"),
+        )?;
+    }
+    stdout.flush()
+}
+
+fn approve(options: &[&OptionView]) -> anyhow::Result<Vec<Uuid>> {
+    print_numbered(options);
+    loop {
+        let answer = prompt("Numbers of the options you approve of, separated by spaces: ")?;
+        let approved = answer
+            .split([' ', ','])
+            .filter(|number| !number.is_empty())
+            .map(|number| {
+                number
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|number| number.checked_sub(1))
+                    .and_then(|index| options.get(index))
+                    .map(|option| option.id)
+                    .ok_or(number)
+            })
+            .collect::<Result<Vec<_>, _>>();
+        match approved {
+            Ok(mut approved) => {
+                // Listing an option twice still only approves of it once
+                let mut seen = HashSet::new();
+                approved.retain(|id| seen.insert(*id));
+                return Ok(approved);
+            }
+            Err(number) => println!("There's no option {}", number),
+        }
+    }
+}
+
+fn score(options: &[&OptionView], max_score: u8) -> anyhow::Result<Vec<(Uuid, u8)>> {
+    println!("Score each option from 0 to {}", max_score);
+    let mut scores = Vec::with_capacity(options.len());
+    for option in options {
+        loop {
+            let answer = prompt(&format!("{}: ", option.text))?;
+            match answer.parse::<u8>() {
+                Ok(score) if score <= max_score => {
+                    scores.push((option.id, score));
+                    break;
+                }
+                _ => println!("Scores go from 0 to {}", max_score),
+            }
+        }
+    }
+    Ok(scores)
+}
+
+fn print_numbered(options: &[&OptionView]) {
+    for (index, option) in options.iter().enumerate() {
+        println!("{:>3}. {}", index + 1, option.text);
+    }
+}
+
+fn prompt(question: &str) -> anyhow::Result<String> {
+    print!("{}", question);
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    if io::stdin().lock().read_line(&mut answer)? == 0 {
+        bail!("Stopped reading before the ballot was finished");
+    }
+    Ok(answer.trim().to_string())
+}
@@ -0,0 +1,195 @@
+use std::{
+    fs,
+    io::{self, IsTerminal, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use api::Client;
+use clap::{Parser, Subcommand};
+use views::{CreateRoomRequest, RoomView, TallyMethod, VotingStage};
+
+pub mod api;
+pub mod ballot;
+pub mod vetoing;
+pub mod views;
+
+/// Runs a What Do? vote from the terminal
+#[derive(Parser, Debug)]
+#[command(version)]
+struct Cli {
+    /// Address of the What Do? server
+    #[arg(
+        long,
+        global = true,
+        env = "WHATDO_SERVER",
+        default_value = "http://localhost:8000"
+    )]
+    server: String,
+
+    /// Session to act as, to carry on as someone who's already in a room
+    #[arg(long, global = true, env = "WHATDO_SESSION")]
+    session: Option<String>,
+
+    /// File to read the session to act as from, like the one saved when a room is created from
+    /// piped input
+    #[arg(
+        long,
+        global = true,
+        env = "WHATDO_SESSION_FILE",
+        conflicts_with = "session"
+    )]
+    session_file: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Creates a room and joins it as its host
+    Create {
+        #[arg(long, short)]
+        nickname: String,
+
+        /// File listing the options: one per line, CSV, JSON or a Markdown list. Read from stdin if
+        /// it isn't given.
+        file: Option<PathBuf>,
+
+        #[arg(long, value_enum)]
+        tally_method: Option<TallyMethod>,
+
+        /// How many options each voter may veto
+        #[arg(long)]
+        veto_limit: Option<u8>,
+
+        /// How many voters have to veto an option before it's struck
+        #[arg(long)]
+        veto_threshold: Option<u8>,
+    },
+    /// Joins a room and takes part in its vote
+    Join {
+        code: String,
+
+        #[arg(long, short)]
+        nickname: String,
+    },
+    /// Prints a room's current results
+    Tally { code: String },
+}
+
+fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+    let session = match (cli.session, &cli.session_file) {
+        (Some(session), _) => Some(session),
+        (None, Some(path)) => Some(
+            fs::read_to_string(path)
+                .with_context(|| format!("Couldn't read {}", path.display()))?
+                .trim()
+                .to_string(),
+        ),
+        (None, None) => None,
+    };
+    let mut client = Client::new(&cli.server, session);
+
+    match cli.command {
+        Command::Create {
+            nickname,
+            file,
+            tally_method,
+            veto_limit,
+            veto_threshold,
+        } => {
+            let from_stdin = file.is_none();
+            let options_text = match file {
+                Some(path) => fs::read_to_string(&path)
+                    .with_context(|| format!("Couldn't read {}", path.display()))?,
+                None => {
+                    let mut text = String::new();
+                    io::stdin().read_to_string(&mut text)?;
+                    text
+                }
+            };
+            let room = client.create_room(&CreateRoomRequest {
+                nickname: nickname.clone(),
+                options_text,
+                tally_method,
+                veto_limit,
+                veto_threshold,
+            })?;
+            println!("Created room {}", room.code.to_ascii_uppercase());
+
+            // Piped options use up stdin, so there's nothing left to read commands from
+            if from_stdin && !io::stdin().is_terminal() {
+                // The session is as good as a password, so it goes in a file rather than on screen
+                let session_file = PathBuf::from(format!("what-do-{}.session", room.code));
+                save_session(&session_file, client.session().unwrap_or_default())
+                    .with_context(|| format!("Couldn't save {}", session_file.display()))?;
+                println!(
+                    "To take part, run: what-do-cli --session-file {} join {} --nickname {}",
+                    session_file.display(),
+                    room.code,
+                    nickname
+                );
+                return Ok(());
+            }
+            take_part(&mut client, room)
+        }
+        Command::Join { code, nickname } => {
+            let room = client.join_room(&code.to_ascii_lowercase(), &nickname)?;
+            take_part(&mut client, room)
+        }
+        Command::Tally { code } => print_tally(&mut client, &code.to_ascii_lowercase()),
+    }
+}
+
+/// Saves the session where only the current user can read it, since anyone who has it can act as them
+fn save_session(path: &Path, session: &str) -> io::Result<()> {
+    let mut file = fs::File::create(path)?;
+    // Locked down before anything is written to it
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    }
+    file.write_all(session.as_bytes())
+}
+
+/// Goes through the room's stages from wherever it's up to, then prints the results
+fn take_part(client: &mut Client, room: RoomView) -> anyhow::Result<()> {
+    let room = match room.voting_stage {
+        VotingStage::Vetoing => match vetoing::run(client, room)? {
+            Some(room) => room,
+            None => return Ok(()),
+        },
+        _ => room,
+    };
+
+    if room.voting_stage != VotingStage::Closed {
+        if !ballot::vote(client, &room)? {
+            println!("No ballot submitted");
+            return Ok(());
+        }
+        println!("Ballot submitted");
+    }
+    print_tally(client, &room.code)
+}
+
+fn print_tally(client: &mut Client, code: &str) -> anyhow::Result<()> {
+    let room = client.get_room(code)?;
+    let tally = client.get_tally(code)?;
+    let status = if room.voting_stage == VotingStage::Closed {
+        "final"
+    } else {
+        "so far"
+    };
+    println!();
+    println!(
+        "Results {} ({}, {} ballots):",
+        status, tally.tally_method, room.ballot_count
+    );
+    for entry in tally.results {
+        println!("{:>3}. {} ({})", entry.place, entry.text, entry.score);
+    }
+    Ok(())
+}
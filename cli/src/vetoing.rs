@@ -0,0 +1,141 @@
+use std::{
+    io::{self, BufRead, Write},
+    thread,
+    time::Duration,
+};
+
+use anyhow::{anyhow, bail};
+use uuid::Uuid;
+
+use crate::{
+    api::Client,
+    views::{RoomView, VotingStage},
+};
+
+/// How often to check whether the room has moved on while waiting for the others
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+const HELP: &str = "Commands:
+  v <number> [reason]  veto an option
+  u <number>           take back your veto
+  a <text>             add an option
+  l                    list the options again
+  ready                say you're done vetoing and wait for everyone else
+  finish               move the room on to voting (host only)
+  q                    quit";
+
+/// Lets the user veto and add options until the room moves on to voting. Returns `None` if they
+/// quit instead.
+pub fn run(client: &mut Client, mut room: RoomView) -> anyhow::Result<Option<RoomView>> {
+    print_options(&room);
+    println!("{}", HELP);
+
+    let stdin = io::stdin();
+    while room.voting_stage == VotingStage::Vetoing {
+        print!("> ");
+        io::stdout().flush()?;
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim();
+        let (command, args) = line
+            .split_once(' ')
+            .map_or((line, ""), |(command, args)| (command, args.trim()));
+
+        let code = room.code.clone();
+        let result = match command {
+            "" => continue,
+            "q" | "quit" => return Ok(None),
+            "h" | "help" | "?" => {
+                println!("{}", HELP);
+                continue;
+            }
+            "l" | "list" => client.get_room(&code),
+            "v" | "veto" => find_option(&room, args)
+                .and_then(|(option, reason)| client.veto(&code, option, reason)),
+            "u" | "undo" => find_option(&room, args).and_then(|(option, _)| {
+                let participant = client.participant()?;
+                client.undo_veto(&code, option, participant)
+            }),
+            "a" | "add" => client.add_option(&code, args),
+            "ready" => client
+                .set_ready(&code, true)
+                .and_then(|room| wait_for_voting(client, room)),
+            "finish" => client.finish_vetoing(&code),
+            _ => Err(anyhow!("Unknown command \"{}\". Type h for help.", command)),
+        };
+
+        match result {
+            Ok(updated) => {
+                room = updated;
+                print_options(&room);
+            }
+            Err(e) => println!("Error: {}", e),
+        }
+    }
+
+    Ok(Some(room))
+}
+
+/// Reads `<number> [rest]`, where the number is an option's position in the printed list
+fn find_option<'a>(room: &RoomView, args: &'a str) -> anyhow::Result<(Uuid, &'a str)> {
+    let (number, rest) = args
+        .split_once(' ')
+        .map_or((args, ""), |(number, rest)| (number, rest.trim()));
+    let Ok(number) = number.parse::<usize>() else {
+        bail!("Give the number of an option from the list");
+    };
+    match number
+        .checked_sub(1)
+        .and_then(|index| room.options.get(index))
+    {
+        Some(option) => Ok((option.id, rest)),
+        None => bail!("There's no option {}", number),
+    }
+}
+
+fn wait_for_voting(client: &mut Client, mut room: RoomView) -> anyhow::Result<RoomView> {
+    println!("Waiting for everyone else to finish vetoing (Ctrl+C to quit)...");
+    while room.voting_stage == VotingStage::Vetoing {
+        thread::sleep(POLL_INTERVAL);
+        room = client.get_room(&room.code)?;
+    }
+    Ok(room)
+}
+
+fn print_options(room: &RoomView) {
+    println!();
+    println!("Room {}", room.code.to_ascii_uppercase());
+    for (index, option) in room.options.iter().enumerate() {
+        let vetoes = option
+            .vetoes
+            .iter()
+            .map(|veto| match &veto.reason {
+                Some(reason) => format!("{} (\"{}\")", room.nickname(&veto.by), reason),
+                None => room.nickname(&veto.by).to_string(),
+            })
+            .collect::<Vec<_>>();
+        let struck = if option.vetoed { " [struck]" } else { "" };
+        if vetoes.is_empty() {
+            println!("{:>3}. {}{}", index + 1, option.text, struck);
+        } else {
+            println!(
+                "{:>3}. {}{} - vetoed by {}",
+                index + 1,
+                option.text,
+                struck,
+                vetoes.join(", ")
+            );
+        }
+        for detail in [&option.description, &option.url].into_iter().flatten() {
+            println!("       {}", detail);
+        }
+    }
+
+    let ready = room.participants.iter().filter(|p| p.ready).count();
+    println!(
+        "{} of the {} people needed are done vetoing",
+        ready, room.required_ready_count
+    );
+}
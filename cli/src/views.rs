@@ -0,0 +1,115 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// The parts of the server's `RoomView` the CLI uses
+#[derive(Deserialize)]
+pub struct RoomView {
+    pub code: String,
+    pub voting_stage: VotingStage,
+    pub options: Vec<OptionView>,
+    pub participants: Vec<ParticipantView>,
+    pub ballot_count: usize,
+    pub required_ready_count: usize,
+    /// The highest score a score ballot can give an option
+    pub max_score: u8,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+pub enum VotingStage {
+    Vetoing,
+    Ranking,
+    Approving,
+    Scoring,
+    Closed,
+}
+
+#[derive(Deserialize)]
+pub struct OptionView {
+    pub id: Uuid,
+    pub text: String,
+    pub description: Option<String>,
+    pub url: Option<String>,
+    pub vetoed: bool,
+    pub vetoes: Vec<VetoView>,
+}
+
+#[derive(Deserialize)]
+pub struct VetoView {
+    pub by: Uuid,
+    pub reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct ParticipantView {
+    pub id: Uuid,
+    pub nickname: String,
+    pub ready: bool,
+}
+
+#[derive(Deserialize)]
+pub struct SessionView {
+    pub participant: Uuid,
+}
+
+#[derive(Deserialize)]
+pub struct TallyView {
+    pub tally_method: String,
+    pub results: Vec<TallyEntryView>,
+}
+
+#[derive(Deserialize)]
+pub struct TallyEntryView {
+    pub text: String,
+    pub score: usize,
+    pub place: usize,
+}
+
+#[derive(Deserialize)]
+pub struct ErrorBody {
+    pub error: String,
+}
+
+/// The voting methods a room can be created with, named as the server expects
+#[derive(Clone, Copy, Debug, Serialize, clap::ValueEnum)]
+pub enum TallyMethod {
+    Borda,
+    InstantRunoff,
+    Schulze,
+    Approval,
+    Star,
+}
+
+#[derive(Serialize)]
+pub struct CreateRoomRequest {
+    pub nickname: String,
+    pub options_text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tally_method: Option<TallyMethod>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub veto_limit: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub veto_threshold: Option<u8>,
+}
+
+/// A ballot of whichever kind the room's voting stage asks for
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum Ballot {
+    Ranked { ranking: Vec<Uuid> },
+    Approval { approved: Vec<Uuid> },
+    Score { scores: Vec<(Uuid, u8)> },
+}
+
+impl RoomView {
+    pub fn nickname(&self, id: &Uuid) -> &str {
+        self.participants
+            .iter()
+            .find(|p| p.id == *id)
+            .map_or("someone who left", |p| p.nickname.as_str())
+    }
+
+    /// The options still in the running, in the room's order
+    pub fn remaining_options(&self) -> Vec<&OptionView> {
+        self.options.iter().filter(|o| !o.vetoed).collect()
+    }
+}
@@ -0,0 +1,74 @@
+use std::sync::{Arc, RwLock};
+
+use axum::{
+    extract::Path,
+    response::sse::{Event, KeepAlive, Sse},
+    Extension,
+};
+use futures_util::{stream, Stream, StreamExt};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::{room_state::RoomState, BroadcastReceiver, RoomEvent, ServerwideSharedState};
+
+use super::{find_room, ApiError, RoomView};
+
+/// The SSE event name for a room's full state
+const ROOM_EVENT_NAME: &str = "room";
+
+struct EventFeed {
+    room: Arc<RwLock<RoomState>>,
+    broadcast_rx: BroadcastReceiver,
+    closed: bool,
+}
+
+/// Follows a room live as Server-Sent Events. The stream starts with a `room` event holding the
+/// room's full state, then sends each `BroadcastMsg` as JSON, the same events the pages listen to.
+/// If the client falls too far behind and events are dropped, it gets another `room` event to
+/// catch up from. The stream ends once the room closes.
+pub async fn room_events(
+    Path(room_code): Path<String>,
+    Extension(state): Extension<ServerwideSharedState>,
+) -> Result<Sse<impl Stream<Item = Result<Event, serde_json::Error>>>, ApiError> {
+    let room = find_room(&state, &room_code)?;
+    // Subscribe under the same lock as taking the starting state, so no event falls in between
+    let (broadcast_rx, first_event) = {
+        let room_state = room.read().unwrap();
+        (
+            room_state.get_broadcast_tx().subscribe(),
+            room_state_event(&room_state),
+        )
+    };
+
+    let feed = EventFeed {
+        room,
+        broadcast_rx,
+        closed: false,
+    };
+    let events = stream::once(async { first_event }).chain(stream::unfold(feed, next_event));
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
+async fn next_event(mut feed: EventFeed) -> Option<(Result<Event, serde_json::Error>, EventFeed)> {
+    if feed.closed {
+        return None;
+    }
+
+    let event = match feed.broadcast_rx.recv().await {
+        Ok(broadcast_msg) => {
+            feed.closed = matches!(broadcast_msg.event, RoomEvent::RoomClosed);
+            Event::default()
+                .id(broadcast_msg.seq.to_string())
+                .json_data(&broadcast_msg)
+        }
+        Err(RecvError::Lagged(_)) => room_state_event(&feed.room.read().unwrap()),
+        Err(RecvError::Closed) => return None,
+    };
+    Some((event, feed))
+}
+
+fn room_state_event(room_state: &RoomState) -> Result<Event, serde_json::Error> {
+    Event::default()
+        .event(ROOM_EVENT_NAME)
+        .id(room_state.event_seq().to_string())
+        .json_data(RoomView::from(room_state))
+}
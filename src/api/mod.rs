@@ -0,0 +1,873 @@
+use std::sync::{Arc, RwLock};
+
+use axum::{
+    extract::Path,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{delete, get, patch, post},
+    Extension, Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    room_state::{
+        ballot::{Ballot, PartialBallotPolicy, MAX_SCORE},
+        import::{import_options, ImportFormat, ImportedOption},
+        option_details::OptionDetails,
+        participant::{Participant, ParticipantId},
+        tally::{TallyBreakdown, TallyMethodKind},
+        tie_break::{TieBreak, TieBreakPolicy},
+        veto_completion::{default_ready_quorum_percent, VetoCompletion},
+        veto_rules::{default_veto_threshold, Veto, MAX_VETO_REASON_LENGTH},
+        RoomSettings, RoomState, VotingStage,
+    },
+    ServerwideSharedState,
+};
+
+pub mod events;
+
+/// JSON endpoints for scripting rooms. They go through the same `ServerState`/`RoomState` methods as
+/// the pages, which send out the same `RoomEvent`s, so anyone in the room sees changes live. Clients are
+/// identified by the same session cookie as browsers, and have to join a room before acting in it.
+/// These routes need to know who's asking, so they go behind the session middleware.
+pub fn router() -> Router {
+    Router::new()
+        .route("/api/rooms", post(create_room))
+        .route("/api/session", get(get_session))
+        .route("/api/rooms/:room_code/participants", post(join_room))
+        .route(
+            "/api/rooms/:room_code/participants/:participant_id",
+            delete(kick),
+        )
+        .route("/api/rooms/:room_code/host", post(transfer_host))
+        .route("/api/rooms/:room_code/lock", post(set_locked))
+        .route("/api/rooms/:room_code/options", post(add_option))
+        .route(
+            "/api/rooms/:room_code/options/:option_id",
+            patch(rename_option).delete(remove_option),
+        )
+        .route("/api/rooms/:room_code/options/:option_id/veto", post(veto))
+        .route(
+            "/api/rooms/:room_code/options/:option_id/vetoes/:participant_id",
+            delete(undo_veto),
+        )
+        .route("/api/rooms/:room_code/ready", post(mark_ready))
+        .route("/api/rooms/:room_code/finish-vetoing", post(finish_vetoing))
+        .route("/api/rooms/:room_code/ballots", post(submit_ballot))
+        .route("/api/rooms/:room_code/close-voting", post(close_voting))
+}
+
+/// JSON endpoints anyone can use without a session, like bots and displays following a room
+pub fn public_router() -> Router {
+    Router::new()
+        .route("/api/import-preview", post(preview_import))
+        .route("/api/rooms/:room_code", get(get_room))
+        .route("/api/rooms/:room_code/events", get(events::room_events))
+        .route("/api/rooms/:room_code/tally", get(get_tally))
+}
+
+#[derive(Debug)]
+pub enum ApiError {
+    NotFound(String),
+    BadRequest(String),
+    Forbidden(String),
+    Conflict(String),
+    Internal(String),
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let (status, message) = match self {
+            ApiError::NotFound(message) => (StatusCode::NOT_FOUND, message),
+            ApiError::BadRequest(message) => (StatusCode::BAD_REQUEST, message),
+            ApiError::Forbidden(message) => (StatusCode::FORBIDDEN, message),
+            ApiError::Conflict(message) => (StatusCode::CONFLICT, message),
+            ApiError::Internal(message) => (StatusCode::INTERNAL_SERVER_ERROR, message),
+        };
+        (status, Json(ErrorBody { error: message })).into_response()
+    }
+}
+
+#[derive(Serialize)]
+struct ErrorBody {
+    error: String,
+}
+
+#[derive(Deserialize)]
+pub struct CreateRoomRequest {
+    pub nickname: String,
+    #[serde(default)]
+    pub options: Vec<String>,
+    /// A list of options in any format `import_options` understands, added after `options`
+    #[serde(default)]
+    pub options_text: String,
+    #[serde(default)]
+    pub tally_method: TallyMethodKind,
+    #[serde(default)]
+    pub tie_break: TieBreakPolicy,
+    #[serde(default)]
+    pub partial_ballots: PartialBallotPolicy,
+    #[serde(default)]
+    pub veto_completion: VetoCompletion,
+    #[serde(default = "default_ready_quorum_percent")]
+    pub ready_quorum_percent: u8,
+    #[serde(default)]
+    pub veto_limit: Option<u8>,
+    #[serde(default = "default_veto_threshold")]
+    pub veto_threshold: u8,
+}
+
+#[derive(Deserialize)]
+pub struct JoinRoomRequest {
+    pub nickname: String,
+}
+
+#[derive(Deserialize)]
+pub struct ReadyRequest {
+    pub ready: bool,
+}
+
+#[derive(Deserialize)]
+pub struct RenameOptionRequest {
+    pub text: String,
+}
+
+#[derive(Deserialize)]
+pub struct VetoRequest {
+    #[serde(default)]
+    pub reason: String,
+}
+
+#[derive(Deserialize)]
+pub struct TransferHostRequest {
+    pub participant: ParticipantId,
+}
+
+#[derive(Deserialize)]
+pub struct LockRequest {
+    pub locked: bool,
+}
+
+#[derive(Deserialize)]
+pub struct AddOptionRequest {
+    pub text: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub url: String,
+    #[serde(default)]
+    pub image_url: String,
+}
+
+/// A ballot of whichever kind the room's voting method uses
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum BallotRequest {
+    Ranked { ranking: Vec<Uuid> },
+    Approval { approved: Vec<Uuid> },
+    Score { scores: Vec<(Uuid, u8)> },
+}
+
+#[derive(Deserialize)]
+pub struct ImportPreviewRequest {
+    pub text: String,
+}
+
+#[derive(Serialize)]
+pub struct SessionView {
+    /// The id the session's holder goes by in rooms
+    pub participant: ParticipantId,
+}
+
+#[derive(Serialize)]
+pub struct ImportPreview {
+    pub format: ImportFormat,
+    pub options: Vec<ImportedOption>,
+}
+
+#[derive(Serialize)]
+pub struct RoomView {
+    pub code: String,
+    pub voting_stage: VotingStage,
+    pub settings: RoomSettings,
+    pub options: Vec<OptionView>,
+    pub participants: Vec<ParticipantView>,
+    pub host: Option<ParticipantId>,
+    pub locked: bool,
+    pub ballot_count: usize,
+    /// How many participants have to be done vetoing before a ready check moves the room on
+    pub required_ready_count: usize,
+    /// The highest score a score ballot can give an option
+    pub max_score: u8,
+    /// The `seq` of the room's latest event, so event stream clients know where this view is from
+    pub event_seq: u64,
+}
+
+#[derive(Serialize)]
+pub struct OptionView {
+    pub id: Uuid,
+    pub text: String,
+    #[serde(flatten)]
+    pub details: OptionDetails,
+    pub vetoed: bool,
+    pub added_by: Option<ParticipantId>,
+    pub vetoes: Vec<Veto>,
+}
+
+#[derive(Serialize)]
+pub struct ParticipantView {
+    pub id: ParticipantId,
+    pub nickname: String,
+    pub ready: bool,
+}
+
+#[derive(Serialize)]
+pub struct TallyView {
+    pub tally_method: TallyMethodKind,
+    pub results: Vec<TallyEntryView>,
+    pub breakdown: TallyBreakdown,
+    pub tie_breaks: Vec<TieBreak>,
+}
+
+#[derive(Serialize)]
+pub struct TallyEntryView {
+    pub id: Uuid,
+    pub text: String,
+    pub score: usize,
+    pub ranks: Vec<usize>,
+    pub place: usize,
+}
+
+impl From<&RoomState> for RoomView {
+    fn from(room: &RoomState) -> Self {
+        RoomView {
+            code: room.code.clone(),
+            voting_stage: room.voting_stage(),
+            settings: room.settings().clone(),
+            options: room
+                .iter_options()
+                .map(|o| OptionView {
+                    id: o.id,
+                    text: o.text().to_string(),
+                    details: o.details().clone(),
+                    vetoed: o.vetoed,
+                    added_by: o.added_by,
+                    vetoes: o.vetoes.clone(),
+                })
+                .collect(),
+            participants: room
+                .iter_participants()
+                .map(|p| ParticipantView {
+                    id: p.id,
+                    nickname: p.nickname().to_string(),
+                    ready: room.is_ready(&p.id),
+                })
+                .collect(),
+            host: room.host(),
+            locked: room.is_locked(),
+            ballot_count: room.ballot_count(),
+            required_ready_count: room.required_ready_count(),
+            max_score: MAX_SCORE,
+            event_seq: room.event_seq(),
+        }
+    }
+}
+
+impl From<&RoomState> for TallyView {
+    fn from(room: &RoomState) -> Self {
+        let outcome = room.tally_votes();
+        TallyView {
+            tally_method: room.settings().tally_method,
+            results: outcome
+                .tallies
+                .iter()
+                .map(|tally| TallyEntryView {
+                    id: tally.id,
+                    text: room.get_option_text(&tally.id),
+                    score: tally.score,
+                    ranks: tally.ranks.clone(),
+                    place: tally.place,
+                })
+                .collect(),
+            breakdown: outcome.breakdown,
+            tie_breaks: outcome.tie_breaks,
+        }
+    }
+}
+
+async fn create_room(
+    Extension(state): Extension<ServerwideSharedState>,
+    Extension(participant): Extension<ParticipantId>,
+    Json(request): Json<CreateRoomRequest>,
+) -> Result<(StatusCode, Json<RoomView>), ApiError> {
+    let creator = Participant::new(participant, &request.nickname)
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+    let settings = RoomSettings {
+        tally_method: request.tally_method,
+        tie_break: request.tie_break,
+        partial_ballots: request.partial_ballots,
+        veto_completion: request.veto_completion,
+        ready_quorum_percent: request.ready_quorum_percent.clamp(1, 100),
+        veto_limit: request.veto_limit,
+        veto_threshold: request.veto_threshold.max(1),
+        ..Default::default()
+    };
+
+    let (_, imported) =
+        import_options(&request.options_text).map_err(|e| ApiError::BadRequest(e.to_string()))?;
+    let options = request
+        .options
+        .into_iter()
+        .map(|text| ImportedOption::new(text.trim().to_string(), OptionDetails::default()))
+        .chain(imported)
+        .collect();
+
+    let mut state = state.write().unwrap();
+    let (_, room, _, _) = state
+        .create_room(options, settings, creator)
+        .map_err(|e| ApiError::Internal(e.to_string()))?;
+    let view = RoomView::from(&*room.read().unwrap());
+    Ok((StatusCode::CREATED, Json(view)))
+}
+
+async fn preview_import(
+    Json(request): Json<ImportPreviewRequest>,
+) -> Result<Json<ImportPreview>, ApiError> {
+    let (format, options) =
+        import_options(&request.text).map_err(|e| ApiError::BadRequest(e.to_string()))?;
+    Ok(Json(ImportPreview { format, options }))
+}
+
+async fn get_session(Extension(participant): Extension<ParticipantId>) -> Json<SessionView> {
+    Json(SessionView { participant })
+}
+
+async fn get_room(
+    Path(room_code): Path<String>,
+    Extension(state): Extension<ServerwideSharedState>,
+) -> Result<Json<RoomView>, ApiError> {
+    let room = find_room(&state, &room_code)?;
+    let view = RoomView::from(&*room.read().unwrap());
+    Ok(Json(view))
+}
+
+async fn join_room(
+    Path(room_code): Path<String>,
+    Extension(state): Extension<ServerwideSharedState>,
+    Extension(participant): Extension<ParticipantId>,
+    Json(request): Json<JoinRoomRequest>,
+) -> Result<Json<RoomView>, ApiError> {
+    let room = find_room(&state, &room_code)?;
+    let mut room = room.write().unwrap();
+    room.join(participant, &request.nickname)
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+    Ok(Json(RoomView::from(&*room)))
+}
+
+async fn add_option(
+    Path(room_code): Path<String>,
+    Extension(state): Extension<ServerwideSharedState>,
+    Extension(participant): Extension<ParticipantId>,
+    Json(request): Json<AddOptionRequest>,
+) -> Result<Json<RoomView>, ApiError> {
+    let room = find_room(&state, &room_code)?;
+    let mut room = room.write().unwrap();
+    require_participant(&room, &participant)?;
+    require_vetoing(&room)?;
+
+    let text = request.text.trim().to_string();
+    if text.is_empty() {
+        return Err(ApiError::BadRequest(
+            "Option text can't be empty".to_string(),
+        ));
+    }
+    let details = OptionDetails::new(&request.description, &request.url, &request.image_url)
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+    room.add_option(participant, text, details)
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+    Ok(Json(RoomView::from(&*room)))
+}
+
+async fn veto(
+    Path((room_code, option_id)): Path<(String, Uuid)>,
+    Extension(state): Extension<ServerwideSharedState>,
+    Extension(participant): Extension<ParticipantId>,
+    request: Option<Json<VetoRequest>>,
+) -> Result<Json<RoomView>, ApiError> {
+    let reason = request.map_or(String::new(), |Json(request)| request.reason);
+    if reason.trim().chars().count() > MAX_VETO_REASON_LENGTH {
+        return Err(ApiError::BadRequest(format!(
+            "Veto reasons can be at most {} characters long",
+            MAX_VETO_REASON_LENGTH
+        )));
+    }
+    let room = find_room(&state, &room_code)?;
+    let mut room = room.write().unwrap();
+    require_participant(&room, &participant)?;
+    require_vetoing(&room)?;
+    if !room.iter_options().any(|o| o.id == option_id) {
+        return Err(ApiError::NotFound(format!(
+            "Option {} not found",
+            option_id
+        )));
+    }
+
+    room.veto(participant, option_id, &reason)
+        .map_err(|e| ApiError::Conflict(e.to_string()))?;
+    Ok(Json(RoomView::from(&*room)))
+}
+
+async fn undo_veto(
+    Path((room_code, option_id, vetoer)): Path<(String, Uuid, ParticipantId)>,
+    Extension(state): Extension<ServerwideSharedState>,
+    Extension(participant): Extension<ParticipantId>,
+) -> Result<Json<RoomView>, ApiError> {
+    let room = find_room(&state, &room_code)?;
+    let mut room = room.write().unwrap();
+    require_participant(&room, &participant)?;
+    require_vetoing(&room)?;
+    if !room.has_vetoed(&vetoer, &option_id) {
+        return Err(ApiError::NotFound(format!(
+            "No veto of option {} by {} found",
+            option_id, vetoer
+        )));
+    }
+
+    room.undo_veto(participant, option_id, vetoer)
+        .map_err(|e| ApiError::Forbidden(e.to_string()))?;
+    Ok(Json(RoomView::from(&*room)))
+}
+
+async fn finish_vetoing(
+    Path(room_code): Path<String>,
+    Extension(state): Extension<ServerwideSharedState>,
+    Extension(participant): Extension<ParticipantId>,
+) -> Result<Json<RoomView>, ApiError> {
+    let room = find_room(&state, &room_code)?;
+    let mut room = room.write().unwrap();
+    require_participant(&room, &participant)?;
+    require_vetoing(&room)?;
+
+    room.finish_vetoing(participant)
+        .map_err(|e| ApiError::Forbidden(e.to_string()))?;
+    Ok(Json(RoomView::from(&*room)))
+}
+
+async fn mark_ready(
+    Path(room_code): Path<String>,
+    Extension(state): Extension<ServerwideSharedState>,
+    Extension(participant): Extension<ParticipantId>,
+    Json(request): Json<ReadyRequest>,
+) -> Result<Json<RoomView>, ApiError> {
+    let room = find_room(&state, &room_code)?;
+    let mut room = room.write().unwrap();
+    require_participant(&room, &participant)?;
+    require_vetoing(&room)?;
+
+    room.mark_ready(participant, request.ready)
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+    Ok(Json(RoomView::from(&*room)))
+}
+
+async fn submit_ballot(
+    Path(room_code): Path<String>,
+    Extension(state): Extension<ServerwideSharedState>,
+    Extension(participant): Extension<ParticipantId>,
+    Json(request): Json<BallotRequest>,
+) -> Result<Json<RoomView>, ApiError> {
+    let room = find_room(&state, &room_code)?;
+    let mut room = room.write().unwrap();
+    require_participant(&room, &participant)?;
+    if !room.is_voting_open() {
+        return Err(ApiError::Conflict(
+            "The room isn't accepting ballots".to_string(),
+        ));
+    }
+
+    let ballot = match request {
+        BallotRequest::Ranked { ranking } => Ballot::Ranked(ranking),
+        BallotRequest::Approval { approved } => Ballot::Approval(approved),
+        BallotRequest::Score { scores } => Ballot::Score(scores),
+    };
+    room.contribute_ballot(participant, ballot)
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+    Ok(Json(RoomView::from(&*room)))
+}
+
+async fn get_tally(
+    Path(room_code): Path<String>,
+    Extension(state): Extension<ServerwideSharedState>,
+) -> Result<Json<TallyView>, ApiError> {
+    let room = find_room(&state, &room_code)?;
+    let view = TallyView::from(&*room.read().unwrap());
+    Ok(Json(view))
+}
+
+async fn kick(
+    Path((room_code, kicked)): Path<(String, ParticipantId)>,
+    Extension(state): Extension<ServerwideSharedState>,
+    Extension(participant): Extension<ParticipantId>,
+) -> Result<Json<RoomView>, ApiError> {
+    let room = find_room(&state, &room_code)?;
+    let mut room = room.write().unwrap();
+    require_host(&room, &participant)?;
+    if !room.is_participant(&kicked) {
+        return Err(ApiError::NotFound(format!(
+            "Participant {} not found",
+            kicked
+        )));
+    }
+
+    room.kick(participant, kicked)
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+    Ok(Json(RoomView::from(&*room)))
+}
+
+async fn transfer_host(
+    Path(room_code): Path<String>,
+    Extension(state): Extension<ServerwideSharedState>,
+    Extension(participant): Extension<ParticipantId>,
+    Json(request): Json<TransferHostRequest>,
+) -> Result<Json<RoomView>, ApiError> {
+    let room = find_room(&state, &room_code)?;
+    let mut room = room.write().unwrap();
+    require_host(&room, &participant)?;
+    if !room.is_participant(&request.participant) {
+        return Err(ApiError::NotFound(format!(
+            "Participant {} not found",
+            request.participant
+        )));
+    }
+
+    room.transfer_host(participant, request.participant)
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+    Ok(Json(RoomView::from(&*room)))
+}
+
+async fn set_locked(
+    Path(room_code): Path<String>,
+    Extension(state): Extension<ServerwideSharedState>,
+    Extension(participant): Extension<ParticipantId>,
+    Json(request): Json<LockRequest>,
+) -> Result<Json<RoomView>, ApiError> {
+    let room = find_room(&state, &room_code)?;
+    let mut room = room.write().unwrap();
+    require_host(&room, &participant)?;
+
+    room.set_locked(participant, request.locked)
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+    Ok(Json(RoomView::from(&*room)))
+}
+
+async fn rename_option(
+    Path((room_code, option_id)): Path<(String, Uuid)>,
+    Extension(state): Extension<ServerwideSharedState>,
+    Extension(participant): Extension<ParticipantId>,
+    Json(request): Json<RenameOptionRequest>,
+) -> Result<Json<RoomView>, ApiError> {
+    let room = find_room(&state, &room_code)?;
+    let mut room = room.write().unwrap();
+    require_participant(&room, &participant)?;
+    require_vetoing(&room)?;
+    require_option_editor(&room, &participant, &option_id)?;
+
+    room.rename_option(participant, option_id, request.text)
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+    Ok(Json(RoomView::from(&*room)))
+}
+
+async fn remove_option(
+    Path((room_code, option_id)): Path<(String, Uuid)>,
+    Extension(state): Extension<ServerwideSharedState>,
+    Extension(participant): Extension<ParticipantId>,
+) -> Result<Json<RoomView>, ApiError> {
+    let room = find_room(&state, &room_code)?;
+    let mut room = room.write().unwrap();
+    require_participant(&room, &participant)?;
+    require_vetoing(&room)?;
+    require_option_editor(&room, &participant, &option_id)?;
+
+    room.remove_option(participant, option_id)
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+    Ok(Json(RoomView::from(&*room)))
+}
+
+async fn close_voting(
+    Path(room_code): Path<String>,
+    Extension(state): Extension<ServerwideSharedState>,
+    Extension(participant): Extension<ParticipantId>,
+) -> Result<Json<RoomView>, ApiError> {
+    let room = find_room(&state, &room_code)?;
+    let mut room = room.write().unwrap();
+    require_host(&room, &participant)?;
+    if !room.is_voting_open() {
+        return Err(ApiError::Conflict("Voting isn't open".to_string()));
+    }
+
+    room.close_voting(participant)
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+    Ok(Json(RoomView::from(&*room)))
+}
+
+pub(crate) fn find_room(
+    state: &ServerwideSharedState,
+    room_code: &str,
+) -> Result<Arc<RwLock<RoomState>>, ApiError> {
+    state
+        .read()
+        .unwrap()
+        .get_room(room_code)
+        .cloned()
+        .ok_or_else(|| ApiError::NotFound(format!("Room \"{}\" not found", room_code)))
+}
+
+fn require_participant(room: &RoomState, participant: &ParticipantId) -> Result<(), ApiError> {
+    if room.is_participant(participant) {
+        Ok(())
+    } else {
+        Err(ApiError::Forbidden(
+            "Join the room with a nickname first".to_string(),
+        ))
+    }
+}
+
+fn require_host(room: &RoomState, participant: &ParticipantId) -> Result<(), ApiError> {
+    if room.is_host(participant) {
+        Ok(())
+    } else {
+        Err(ApiError::Forbidden("Only the host can do that".to_string()))
+    }
+}
+
+fn require_option_editor(
+    room: &RoomState,
+    participant: &ParticipantId,
+    option_id: &Uuid,
+) -> Result<(), ApiError> {
+    if room.get_option(option_id).is_none() {
+        Err(ApiError::NotFound(format!(
+            "Option {} not found",
+            option_id
+        )))
+    } else if room.can_edit_option(participant, option_id) {
+        Ok(())
+    } else {
+        Err(ApiError::Forbidden(
+            "Only whoever added that option or the host can change it".to_string(),
+        ))
+    }
+}
+
+fn require_vetoing(room: &RoomState) -> Result<(), ApiError> {
+    match room.voting_stage() {
+        VotingStage::Vetoing => Ok(()),
+        _ => Err(ApiError::Conflict(
+            "The room has finished vetoing".to_string(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server_state::ServerState;
+
+    fn server() -> ServerwideSharedState {
+        Arc::new(RwLock::new(ServerState::default()))
+    }
+
+    /// Creates a room with the given options through the API, returning its view and its host
+    async fn create(state: &ServerwideSharedState, options: &[&str]) -> (RoomView, ParticipantId) {
+        let host = ParticipantId::new();
+        let request = CreateRoomRequest {
+            nickname: "Host".to_string(),
+            options: options.iter().map(|text| text.to_string()).collect(),
+            options_text: String::new(),
+            tally_method: TallyMethodKind::default(),
+            tie_break: TieBreakPolicy::default(),
+            partial_ballots: PartialBallotPolicy::default(),
+            veto_completion: VetoCompletion::default(),
+            ready_quorum_percent: default_ready_quorum_percent(),
+            veto_limit: None,
+            veto_threshold: default_veto_threshold(),
+        };
+        let (status, Json(view)) =
+            create_room(Extension(state.clone()), Extension(host), Json(request))
+                .await
+                .unwrap();
+        assert_eq!(status, StatusCode::CREATED);
+        (view, host)
+    }
+
+    async fn join(state: &ServerwideSharedState, code: &str, nickname: &str) -> ParticipantId {
+        let participant = ParticipantId::new();
+        let Json(view) = join_room(
+            Path(code.to_string()),
+            Extension(state.clone()),
+            Extension(participant),
+            Json(JoinRoomRequest {
+                nickname: nickname.to_string(),
+            }),
+        )
+        .await
+        .unwrap();
+        assert!(view.participants.iter().any(|p| p.id == participant));
+        participant
+    }
+
+    async fn start_voting(state: &ServerwideSharedState, code: &str, host: ParticipantId) {
+        let Json(view) = finish_vetoing(
+            Path(code.to_string()),
+            Extension(state.clone()),
+            Extension(host),
+        )
+        .await
+        .unwrap();
+        assert!(matches!(view.voting_stage, VotingStage::Ranking));
+    }
+
+    #[tokio::test]
+    async fn created_rooms_can_be_joined_and_fetched_by_code() {
+        let state = server();
+        let (view, host) = create(&state, &["Bowling", " Picnic "]).await;
+        assert_eq!(view.host, Some(host));
+        assert_eq!(
+            view.options
+                .iter()
+                .map(|o| o.text.as_str())
+                .collect::<Vec<_>>(),
+            ["Bowling", "Picnic"]
+        );
+
+        let ann = join(&state, &view.code, "Ann").await;
+        let Json(fetched) = get_room(Path(view.code.to_uppercase()), Extension(state.clone()))
+            .await
+            .unwrap();
+
+        assert!(fetched.participants.iter().any(|p| p.id == ann));
+        assert_eq!(fetched.participants.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn unknown_rooms_and_options_are_not_found() {
+        let state = server();
+        let (view, host) = create(&state, &["Bowling"]).await;
+
+        let room = get_room(Path("nope".to_string()), Extension(state.clone())).await;
+        assert!(matches!(room, Err(ApiError::NotFound(_))));
+
+        let veto = veto(
+            Path((view.code, Uuid::new_v4())),
+            Extension(state.clone()),
+            Extension(host),
+            None,
+        )
+        .await;
+        assert!(matches!(veto, Err(ApiError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn only_participants_can_act_in_a_room() {
+        let state = server();
+        let (view, _) = create(&state, &["Bowling"]).await;
+
+        let added = add_option(
+            Path(view.code),
+            Extension(state.clone()),
+            Extension(ParticipantId::new()),
+            Json(AddOptionRequest {
+                text: "Picnic".to_string(),
+                description: String::new(),
+                url: String::new(),
+                image_url: String::new(),
+            }),
+        )
+        .await;
+
+        assert!(matches!(added, Err(ApiError::Forbidden(_))));
+    }
+
+    #[tokio::test]
+    async fn only_the_host_can_finish_vetoing() {
+        let state = server();
+        let (view, host) = create(&state, &["Bowling"]).await;
+        let ann = join(&state, &view.code, "Ann").await;
+
+        let by_ann = finish_vetoing(
+            Path(view.code.clone()),
+            Extension(state.clone()),
+            Extension(ann),
+        )
+        .await;
+        assert!(matches!(by_ann, Err(ApiError::Forbidden(_))));
+
+        let Json(view) = finish_vetoing(Path(view.code), Extension(state.clone()), Extension(host))
+            .await
+            .unwrap();
+        assert!(matches!(view.voting_stage, VotingStage::Ranking));
+    }
+
+    #[tokio::test]
+    async fn vetoes_conflict_once_vetoing_is_over() {
+        let state = server();
+        let (view, host) = create(&state, &["Bowling", "Picnic"]).await;
+        let option = view.options[0].id;
+        let code = view.code;
+
+        let Json(vetoed) = veto(
+            Path((code.clone(), option)),
+            Extension(state.clone()),
+            Extension(host),
+            Some(Json(VetoRequest {
+                reason: "Too loud".to_string(),
+            })),
+        )
+        .await
+        .unwrap();
+        assert!(vetoed.options[0].vetoed);
+        assert_eq!(vetoed.options[0].vetoes[0].reason(), Some("Too loud"));
+
+        start_voting(&state, &code, host).await;
+        let late = veto(
+            Path((code, view.options[1].id)),
+            Extension(state.clone()),
+            Extension(host),
+            None,
+        )
+        .await;
+        assert!(matches!(late, Err(ApiError::Conflict(_))));
+    }
+
+    #[tokio::test]
+    async fn ballots_are_only_taken_while_voting_is_open() {
+        let state = server();
+        let (view, host) = create(&state, &["Bowling", "Picnic"]).await;
+        let ranking: Vec<Uuid> = view.options.iter().map(|o| o.id).collect();
+        let ballot = || {
+            Json(BallotRequest::Ranked {
+                ranking: ranking.clone(),
+            })
+        };
+
+        let early = submit_ballot(
+            Path(view.code.clone()),
+            Extension(state.clone()),
+            Extension(host),
+            ballot(),
+        )
+        .await;
+        assert!(matches!(early, Err(ApiError::Conflict(_))));
+
+        start_voting(&state, &view.code, host).await;
+        let Json(voted) = submit_ballot(
+            Path(view.code),
+            Extension(state.clone()),
+            Extension(host),
+            ballot(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(voted.ballot_count, 1);
+    }
+}
@@ -1,36 +1,57 @@
 use crate::{
     pages::{
-        error_page::ErrorPage, ranking_page::RankingMsg, results_page::ResultsMsg,
-        room_choice_page::RoomChoiceMsg, veto_page::VetoMsg, AppPage, AppUpdateResponse,
+        approval_page::ApprovalMsg,
+        error_page::ErrorPage,
+        get_results_page_response,
+        join_page::JoinMsg,
+        ranking_page::RankingMsg,
+        results_page::ResultsMsg,
+        room_choice_page::{RoomChoiceMsg, RoomChoicePage},
+        roster::{update_as_host, HostMsg},
+        score_page::ScoreMsg,
+        veto_page::VetoMsg,
+        AppPage, AppUpdateResponse,
     },
-    BroadcastMsg, BroadcastReceiver, ServerwideSharedState,
+    room_state::{participant::ParticipantId, presence::PresenceGuard, VotingStage},
+    BroadcastMsg, BroadcastReceiver, BroadcastReceiverSender, RoomEvent, ServerwideSharedState,
 };
 use axum::http::{HeaderMap, Uri};
 use axum_live_view::{
     event_data::EventData,
+    js_command,
     live_view::{Updated, ViewHandle},
     Html, LiveView,
 };
 use serde::{Deserialize, Serialize};
-use std::sync::mpsc;
-use tokio::sync::broadcast;
+use tokio::sync::{broadcast::error::RecvError, mpsc};
 use tracing::warn;
 
 pub struct App {
     shared_state: ServerwideSharedState,
-    broadcast_rx_tx: Option<mpsc::Sender<broadcast::Receiver<BroadcastMsg>>>,
+    broadcast_rx_tx: Option<BroadcastReceiverSender>,
     broadcast_rx: Option<BroadcastReceiver>,
     current_page: Box<dyn AppPage + Send + Sync>,
+    participant: ParticipantId,
+    presence: Option<PresenceGuard>,
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
 pub enum AppMsg {
     RoomChoiceMsg(RoomChoiceMsg),
+    JoinMsg(JoinMsg),
     VetoMsg(VetoMsg),
     RankingMsg(RankingMsg),
+    ApprovalMsg(ApprovalMsg),
+    ScoreMsg(ScoreMsg),
     ResultsMsg(ResultsMsg),
+    HostMsg(HostMsg),
     Submit,
     Update,
+    Kicked,
+    VotingClosed,
+    RoomClosed,
+    /// Some room events were missed, so the view needs to catch up with the room's state
+    Resync,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -43,12 +64,42 @@ impl App {
         shared_state: ServerwideSharedState,
         page: Box<dyn AppPage + Sync + Send>,
         broadcast_rx: Option<BroadcastReceiver>,
+        participant: ParticipantId,
     ) -> Self {
         Self {
             shared_state,
             broadcast_rx_tx: None,
             broadcast_rx,
             current_page: page,
+            participant,
+            presence: None,
+        }
+    }
+
+    /// Sends the view back to the home page, e.g. because its room is gone
+    fn leave_room(mut self, reason: &str) -> Updated<Self> {
+        self.current_page = Box::new(RoomChoicePage::new(
+            self.participant,
+            Some(reason.to_string()),
+        ));
+        self.sync_presence();
+        Updated::new(self).with_all(vec![js_command::history_push_state(Uri::from_static("/"))])
+    }
+
+    /// Keeps this view counted as present in whichever room its current page belongs to. The guard is
+    /// dropped along with the view when the connection closes.
+    fn sync_presence(&mut self) {
+        let room_state = self.current_page.room_state();
+        let in_same_room = match (&self.presence, room_state) {
+            (Some(presence), Some(room_state)) => presence.is_for(room_state),
+            (None, None) => true,
+            _ => false,
+        };
+        if !in_same_room {
+            // Leave the old room before joining the new one
+            self.presence = None;
+            self.presence = room_state
+                .map(|room_state| PresenceGuard::new(room_state.clone(), self.participant));
         }
     }
 }
@@ -57,64 +108,102 @@ impl LiveView for App {
     type Message = AppMsg;
 
     fn mount(&mut self, _: Uri, _: &HeaderMap, handle: ViewHandle<Self::Message>) {
-        let (broadcast_rx_tx, broadcast_rx_rx) = mpsc::channel();
+        self.sync_presence();
+        let (broadcast_rx_tx, mut broadcast_rx_rx) = mpsc::unbounded_channel();
         if let Some(broadcast_rx) = self.broadcast_rx.take() {
             broadcast_rx_tx.send(broadcast_rx).unwrap();
         }
         self.broadcast_rx_tx = Some(broadcast_rx_tx);
+        let participant = self.participant;
         tokio::spawn(async move {
-            let recv_result = broadcast_rx_rx.recv();
-            match recv_result {
-                Ok(mut broadcast_rx) => {
-                    while let Ok(broadcast_msg) = broadcast_rx.recv().await {
-                        match broadcast_msg {
-                            BroadcastMsg::UpdatedVetos => {
-                                if handle
-                                    .send(AppMsg::VetoMsg(VetoMsg::VetosUpdated))
-                                    .await
-                                    .is_err()
-                                {
-                                    break;
-                                }
-                            }
-                            BroadcastMsg::FinishedVetoing => {
-                                if handle
-                                    .send(AppMsg::VetoMsg(VetoMsg::OtherUserFinishedVetoing))
-                                    .await
-                                    .is_err()
-                                {
-                                    break;
-                                }
-                            }
-                            BroadcastMsg::UpdatedVotes => {
-                                if handle
-                                    .send(AppMsg::ResultsMsg(ResultsMsg::ResultsUpdated))
-                                    .await
-                                    .is_err()
-                                {
-                                    break;
-                                }
+            // A new receiver arrives each time this view joins a room, e.g. after its previous room closed
+            while let Some(mut broadcast_rx) = broadcast_rx_rx.recv().await {
+                let mut last_seq = None;
+                loop {
+                    let (msg, stop) = match broadcast_rx.recv().await {
+                        Ok(BroadcastMsg { seq, event }) => {
+                            let missed_some = last_seq.is_some_and(|last_seq| seq != last_seq + 1);
+                            last_seq = Some(seq);
+                            if missed_some && handle.send(AppMsg::Resync).await.is_err() {
+                                return;
                             }
+                            event_to_msg(event, participant)
+                        }
+                        // This view fell far enough behind that some events were dropped, so rather
+                        // than working out what they were, catch up from the room's current state
+                        Err(RecvError::Lagged(_)) => {
+                            last_seq = None;
+                            (AppMsg::Resync, false)
                         }
+                        Err(RecvError::Closed) => break,
+                    };
+                    if handle.send(msg).await.is_err() {
+                        return;
+                    }
+                    if stop {
+                        break;
                     }
                 }
-                Err(_) => (),
             }
         });
     }
 
     fn update(mut self, msg: AppMsg, data: Option<EventData>) -> Updated<Self> {
-        let tx = self.broadcast_rx_tx.as_mut().unwrap();
-        match self
-            .current_page
-            .update(msg, data, &mut self.shared_state, tx)
-        {
+        match msg {
+            AppMsg::RoomClosed => {
+                return self.leave_room("The room was closed after being inactive for too long")
+            }
+            AppMsg::Kicked => return self.leave_room("The host removed you from the room"),
+            _ => (),
+        }
+
+        let room_state = self.current_page.room_state().cloned();
+        let response = match (msg, room_state) {
+            (AppMsg::HostMsg(msg), Some(room_state)) => {
+                update_as_host(msg, &room_state, self.participant).map(|_| (None, None).into())
+            }
+            (AppMsg::VotingClosed, Some(room_state)) => {
+                let room_code = room_state.read().unwrap().code.clone();
+                get_results_page_response(&room_code, &room_state, self.participant)
+            }
+            (AppMsg::Resync, Some(room_state)) => {
+                let (room_code, voting_stage) = {
+                    let room_state = room_state.read().unwrap();
+                    (room_state.code.clone(), room_state.voting_stage())
+                };
+                match voting_stage {
+                    VotingStage::Closed => {
+                        get_results_page_response(&room_code, &room_state, self.participant)
+                    }
+                    // Moves a veto page on to voting if vetoing finished while this view was behind.
+                    // Other pages render straight from the room's state, so re-rendering is enough.
+                    VotingStage::Vetoing => Ok((None, None).into()),
+                    _ => {
+                        let tx = self.broadcast_rx_tx.as_mut().unwrap();
+                        self.current_page.update(
+                            AppMsg::VetoMsg(VetoMsg::OtherUserFinishedVetoing),
+                            None,
+                            &mut self.shared_state,
+                            tx,
+                        )
+                    }
+                }
+            }
+            (msg, _) => {
+                let tx = self.broadcast_rx_tx.as_mut().unwrap();
+                self.current_page
+                    .update(msg, data, &mut self.shared_state, tx)
+            }
+        };
+
+        match response {
             Ok(AppUpdateResponse {
                 next_page,
                 js_commands,
             }) => {
                 if let Some(page) = next_page {
                     self.current_page = page;
+                    self.sync_presence();
                 }
 
                 let mut updated = Updated::new(self);
@@ -126,6 +215,7 @@ impl LiveView for App {
             }
             Err(e) => {
                 self.current_page = Box::new(ErrorPage::new(e));
+                self.sync_presence();
                 Updated::new(self)
             }
         }
@@ -135,3 +225,31 @@ impl LiveView for App {
         self.current_page.render()
     }
 }
+
+/// The message a room event means for a view of the room, and whether the view stops listening to
+/// the room after it
+fn event_to_msg(event: RoomEvent, participant: ParticipantId) -> (AppMsg, bool) {
+    match event {
+        RoomEvent::OptionAdded { .. }
+        | RoomEvent::OptionRenamed { .. }
+        | RoomEvent::OptionRemoved { .. }
+        | RoomEvent::OptionVetoed { .. }
+        | RoomEvent::VetoUndone { .. }
+        | RoomEvent::VetosReset { .. }
+        | RoomEvent::ReadyChanged { .. } => (AppMsg::VetoMsg(VetoMsg::VetosUpdated), false),
+        RoomEvent::FinishedVetoing => (AppMsg::VetoMsg(VetoMsg::OtherUserFinishedVetoing), false),
+        RoomEvent::BallotSubmitted { .. } => {
+            (AppMsg::ResultsMsg(ResultsMsg::ResultsUpdated), false)
+        }
+        RoomEvent::ParticipantJoined { .. }
+        | RoomEvent::PresenceChanged { .. }
+        | RoomEvent::HostChanged { .. }
+        | RoomEvent::LockChanged { .. } => (AppMsg::Update, false),
+        RoomEvent::ParticipantKicked {
+            participant: kicked,
+        } if kicked == participant => (AppMsg::Kicked, true),
+        RoomEvent::ParticipantKicked { .. } => (AppMsg::Update, false),
+        RoomEvent::VotingClosed => (AppMsg::VotingClosed, false),
+        RoomEvent::RoomClosed => (AppMsg::RoomClosed, true),
+    }
+}
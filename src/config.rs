@@ -0,0 +1,59 @@
+use std::{net::IpAddr, path::PathBuf, sync::Arc, time::Duration};
+
+use clap::Parser;
+use tracing::info;
+
+use crate::storage::{noop::NoopStorage, sqlite::SqliteStorage, RoomStorage};
+
+const DEFAULT_ROOM_TTL_MINUTES: u64 = 24 * 60;
+const MAX_ROOM_TTL_MINUTES: u64 = 365 * 24 * 60;
+
+/// Server settings, read from command-line flags or environment variables
+#[derive(Parser, Debug)]
+#[command(
+    version,
+    about = "Helps a group decide what to do through a voting process"
+)]
+pub struct Config {
+    /// Address to listen on. Ignored when running on shuttle.
+    #[arg(long, env = "WHATDO_BIND_ADDRESS", default_value = "0.0.0.0")]
+    pub bind_address: IpAddr,
+
+    /// Port to listen on. Ignored when running on shuttle.
+    #[arg(long, short, env = "WHATDO_PORT", default_value_t = 8000)]
+    pub port: u16,
+
+    /// SQLite database file to save rooms to. Rooms are only kept in memory if this isn't set.
+    #[arg(long, env = "WHATDO_DATABASE_PATH")]
+    pub database_path: Option<PathBuf>,
+
+    /// How long a room may go without activity before it's closed, in minutes. At most a year.
+    #[arg(
+        long,
+        env = "WHATDO_ROOM_TTL_MINUTES",
+        default_value_t = DEFAULT_ROOM_TTL_MINUTES,
+        value_parser = clap::value_parser!(u64).range(1..=MAX_ROOM_TTL_MINUTES)
+    )]
+    pub room_ttl_minutes: u64,
+}
+
+impl Config {
+    /// Reads the configuration from environment variables alone, for when there's no command line to speak of
+    pub fn from_env() -> Self {
+        Self::parse_from([env!("CARGO_PKG_NAME")])
+    }
+
+    pub fn room_ttl(&self) -> Duration {
+        Duration::from_secs(self.room_ttl_minutes.saturating_mul(60))
+    }
+
+    pub fn storage(&self) -> anyhow::Result<Arc<dyn RoomStorage>> {
+        match &self.database_path {
+            Some(path) => {
+                info!("Saving rooms to {}", path.display());
+                Ok(Arc::new(SqliteStorage::open(path)?))
+            }
+            None => Ok(Arc::new(NoopStorage)),
+        }
+    }
+}
@@ -0,0 +1,765 @@
+use axum::{
+    extract::Path,
+    http::header::{CONTENT_DISPOSITION, CONTENT_TYPE},
+    response::{IntoResponse, Response},
+    Extension,
+};
+use itertools::Itertools;
+use ordinal::Ordinal;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::{
+    api::{find_room, ApiError},
+    room_state::{
+        ballot::BallotKind,
+        tally::{TallyBreakdown, TallyMethodKind},
+        tie_break::TieBreakPolicy,
+        veto_rules::describe_veto_rules,
+        RoomSettings, RoomState, VotingStage,
+    },
+    ServerwideSharedState,
+};
+
+/// A room's whole vote in one place, for archiving it outside the app. Text is as it was entered,
+/// so it has to be escaped for whatever format it's written out in.
+#[derive(Serialize)]
+pub struct ResultsExport {
+    pub room: String,
+    pub voting_stage: VotingStage,
+    pub settings: RoomSettings,
+    pub participants: Vec<String>,
+    pub results: Vec<ResultExport>,
+    pub tie_breaks: Vec<TieBreakExport>,
+    /// How the tally was worked out, with options referred to by id
+    pub breakdown: TallyBreakdown,
+    pub ballots: Vec<BallotExport>,
+    pub vetoes: Vec<VetoExport>,
+}
+
+#[derive(Serialize)]
+pub struct ResultExport {
+    pub id: Uuid,
+    pub place: usize,
+    pub option: String,
+    pub score: usize,
+    pub ranks: Vec<usize>,
+}
+
+#[derive(Serialize)]
+pub struct TieBreakExport {
+    pub tied: Vec<String>,
+    pub policy: TieBreakPolicy,
+    pub resolved: bool,
+}
+
+#[derive(Serialize)]
+pub struct BallotExport {
+    pub voter: String,
+    pub choices: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct VetoExport {
+    pub option: String,
+    pub by: String,
+    pub reason: Option<String>,
+    /// Whether the option ended up struck, which may take several vetoes
+    pub struck: bool,
+}
+
+impl From<&RoomState> for ResultsExport {
+    fn from(room: &RoomState) -> Self {
+        let outcome = room.tally_votes();
+        ResultsExport {
+            room: room.code.clone(),
+            voting_stage: room.voting_stage(),
+            settings: room.settings().clone(),
+            participants: room
+                .iter_participants()
+                .map(|p| p.nickname().to_string())
+                .collect(),
+            results: outcome
+                .tallies
+                .iter()
+                .map(|tally| ResultExport {
+                    id: tally.id,
+                    place: tally.place,
+                    option: room.get_option_text(&tally.id),
+                    score: tally.score,
+                    ranks: tally.ranks.clone(),
+                })
+                .collect(),
+            tie_breaks: outcome
+                .tie_breaks
+                .iter()
+                .map(|tie_break| TieBreakExport {
+                    tied: tie_break
+                        .tied
+                        .iter()
+                        .map(|id| room.get_option_text(id))
+                        .collect(),
+                    policy: tie_break.policy,
+                    resolved: tie_break.resolved,
+                })
+                .collect(),
+            breakdown: outcome.breakdown,
+            ballots: room
+                .iter_text_votes()
+                .map(|(voter, choices)| BallotExport {
+                    voter: room.get_participant_nickname(&voter),
+                    choices,
+                })
+                .collect(),
+            vetoes: room
+                .iter_options()
+                .flat_map(|option| {
+                    option.vetoes.iter().map(|veto| VetoExport {
+                        option: option.text().to_string(),
+                        by: room.get_participant_nickname(&veto.by),
+                        reason: veto.reason().map(str::to_string),
+                        struck: option.vetoed,
+                    })
+                })
+                .collect(),
+        }
+    }
+}
+
+impl ResultsExport {
+    fn status_text(&self) -> &'static str {
+        match self.voting_stage {
+            VotingStage::Vetoing => "Still vetoing",
+            VotingStage::Ranking | VotingStage::Approving | VotingStage::Scoring => {
+                "Voting still open"
+            }
+            VotingStage::Closed => "Final",
+        }
+    }
+
+    /// The room's settings as (name, value) pairs
+    fn metadata(&self) -> Vec<(&'static str, String)> {
+        let settings = &self.settings;
+        let mut metadata = vec![
+            ("Room", self.room.clone()),
+            ("Status", self.status_text().to_string()),
+            (
+                "Voting method",
+                settings.tally_method.display_name().to_string(),
+            ),
+            ("Tie-break", settings.tie_break.display_name().to_string()),
+        ];
+        if settings.tie_break == TieBreakPolicy::SeededRandom {
+            metadata.push(("Tie-break seed", settings.tie_break_seed.to_string()));
+        }
+        if is_ranked(settings.tally_method) {
+            metadata.push((
+                "Partial ballots",
+                settings.partial_ballots.display_name().to_string(),
+            ));
+        }
+        metadata.extend([
+            (
+                "Veto rules",
+                describe_veto_rules(settings.veto_limit, settings.veto_threshold),
+            ),
+            ("Participants", self.participants.join(", ")),
+            ("Ballots", self.ballots.len().to_string()),
+        ]);
+        metadata
+    }
+
+    fn tie_break_text(tie_break: &TieBreakExport) -> String {
+        let outcome = if tie_break.resolved {
+            "broken by"
+        } else {
+            "left unbroken by"
+        };
+        format!(
+            "Tie between {} {} {}",
+            tie_break.tied.join(", "),
+            outcome,
+            tie_break.policy.display_name().to_ascii_lowercase()
+        )
+    }
+
+    /// A spreadsheet-friendly CSV: the room's details, then the results, ballots and vetoes, each
+    /// with its own header row and separated by a blank line
+    pub fn to_csv(&self) -> String {
+        let mut rows: Vec<Vec<String>> = self
+            .metadata()
+            .into_iter()
+            .map(|(name, value)| vec![name.to_string(), value])
+            .collect();
+
+        rows.push(Vec::new());
+        rows.push(vec![
+            "Place".into(),
+            "Option".into(),
+            "Score".into(),
+            "Ranks".into(),
+        ]);
+        rows.extend(self.results.iter().map(|result| {
+            vec![
+                result.place.to_string(),
+                result.option.clone(),
+                result.score.to_string(),
+                get_ranks_text(&result.ranks),
+            ]
+        }));
+        rows.extend(
+            self.tie_breaks
+                .iter()
+                .map(|tie_break| vec![Self::tie_break_text(tie_break)]),
+        );
+
+        // Only ranked ballots put their choices in an order that means anything
+        let ranked = is_ranked(self.settings.tally_method);
+        rows.push(Vec::new());
+        rows.push(if ranked {
+            vec!["Voter".into(), "Position".into(), "Choice".into()]
+        } else {
+            vec!["Voter".into(), "Choice".into()]
+        });
+        rows.extend(self.ballots.iter().flat_map(|ballot| {
+            ballot
+                .choices
+                .iter()
+                .enumerate()
+                .map(move |(index, choice)| {
+                    if ranked {
+                        vec![
+                            ballot.voter.clone(),
+                            (index + 1).to_string(),
+                            choice.clone(),
+                        ]
+                    } else {
+                        vec![ballot.voter.clone(), choice.clone()]
+                    }
+                })
+        }));
+
+        rows.push(Vec::new());
+        rows.push(vec![
+            "Option".into(),
+            "Vetoed by".into(),
+            "Reason".into(),
+            "Struck".into(),
+        ]);
+        rows.extend(self.vetoes.iter().map(|veto| {
+            vec![
+                veto.option.clone(),
+                veto.by.clone(),
+                veto.reason.clone().unwrap_or_default(),
+                if veto.struck { "yes" } else { "no" }.to_string(),
+            ]
+        }));
+
+        rows.iter()
+            .map(|row| row.iter().map(|field| escape_csv(field)).join(","))
+            .map(|line| line + "\n")
+            .collect()
+    }
+
+    /// Markdown ready to paste into a wiki page
+    pub fn to_markdown(&self) -> String {
+        let mut lines = vec![format!("# Results for room {}", self.room), String::new()];
+        lines.extend(
+            self.metadata()
+                .into_iter()
+                .skip(1)
+                .map(|(name, value)| format!("- **{}:** {}", name, escape_markdown(&value))),
+        );
+
+        lines.extend([
+            String::new(),
+            "## Results".to_string(),
+            String::new(),
+            "| Place | Option | Score | Ranks |".to_string(),
+            "| ---: | --- | ---: | --- |".to_string(),
+        ]);
+        lines.extend(self.results.iter().map(|result| {
+            format!(
+                "| {} | {} | {} | {} |",
+                result.place,
+                escape_markdown(&result.option),
+                result.score,
+                get_ranks_text(&result.ranks)
+            )
+        }));
+        if !self.tie_breaks.is_empty() {
+            lines.push(String::new());
+            lines.extend(self.tie_breaks.iter().map(|tie_break| {
+                format!("*{}*", escape_markdown(&Self::tie_break_text(tie_break)))
+            }));
+        }
+
+        lines.extend([String::new(), "## Ballots".to_string()]);
+        let ranked = is_ranked(self.settings.tally_method);
+        for ballot in &self.ballots {
+            lines.extend([
+                String::new(),
+                format!("### {}", escape_markdown(&ballot.voter)),
+                String::new(),
+            ]);
+            lines.extend(ballot.choices.iter().enumerate().map(|(index, choice)| {
+                if ranked {
+                    format!("{}. {}", index + 1, escape_markdown(choice))
+                } else {
+                    format!("- {}", escape_markdown(choice))
+                }
+            }));
+        }
+
+        if !self.vetoes.is_empty() {
+            lines.extend([String::new(), "## Vetoes".to_string(), String::new()]);
+            lines.extend(self.vetoes.iter().map(|veto| {
+                let option = if veto.struck {
+                    format!("~~{}~~", escape_markdown(&veto.option))
+                } else {
+                    escape_markdown(&veto.option)
+                };
+                match &veto.reason {
+                    Some(reason) => format!(
+                        "- {}: vetoed by {} (\"{}\")",
+                        option,
+                        escape_markdown(&veto.by),
+                        escape_markdown(reason)
+                    ),
+                    None => format!("- {}: vetoed by {}", option, escape_markdown(&veto.by)),
+                }
+            }));
+        }
+
+        lines.join("\n") + "\n"
+    }
+}
+
+pub async fn results_json(
+    Path(room_code): Path<String>,
+    Extension(state): Extension<ServerwideSharedState>,
+) -> Result<Response, ApiError> {
+    let export = get_export(&state, &room_code)?;
+    let json =
+        serde_json::to_string_pretty(&export).map_err(|e| ApiError::Internal(e.to_string()))?;
+    Ok(download(&export.room, "json", "application/json", json))
+}
+
+pub async fn results_csv(
+    Path(room_code): Path<String>,
+    Extension(state): Extension<ServerwideSharedState>,
+) -> Result<Response, ApiError> {
+    let export = get_export(&state, &room_code)?;
+    Ok(download(
+        &export.room,
+        "csv",
+        "text/csv; charset=utf-8",
+        export.to_csv(),
+    ))
+}
+
+pub async fn results_markdown(
+    Path(room_code): Path<String>,
+    Extension(state): Extension<ServerwideSharedState>,
+) -> Result<Response, ApiError> {
+    let export = get_export(&state, &room_code)?;
+    Ok(download(
+        &export.room,
+        "md",
+        "text/markdown; charset=utf-8",
+        export.to_markdown(),
+    ))
+}
+
+fn get_export(state: &ServerwideSharedState, room_code: &str) -> Result<ResultsExport, ApiError> {
+    let room = find_room(state, room_code)?;
+    let export = ResultsExport::from(&*room.read().unwrap());
+    Ok(export)
+}
+
+fn download(room_code: &str, extension: &str, content_type: &str, body: String) -> Response {
+    let disposition = format!(
+        "attachment; filename=\"what-do-{}-results.{}\"",
+        room_code, extension
+    );
+    (
+        [
+            (CONTENT_TYPE, content_type.to_string()),
+            (CONTENT_DISPOSITION, disposition),
+        ],
+        body,
+    )
+        .into_response()
+}
+
+fn is_ranked(tally_method: TallyMethodKind) -> bool {
+    tally_method.ballot_kind() == BallotKind::Ranked
+}
+
+fn get_ranks_text(ranks: &[usize]) -> String {
+    ranks.iter().map(|r| Ordinal(*r).to_string()).join(", ")
+}
+
+/// Quotes a CSV field if it has anything in it that would otherwise split it up. Fields that a
+/// spreadsheet would run as a formula get a leading `'` first, so an option named `=HYPERLINK(...)`
+/// shows up as text instead of doing something when the export is opened.
+fn escape_csv(field: &str) -> String {
+    let field = if field.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{}", field)
+    } else {
+        field.to_string()
+    };
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field
+    }
+}
+
+/// Backslash-escapes characters that Markdown would treat as formatting, and keeps everything on one
+/// line so text can't break out of a list item or table cell. A list marker at the start, like `- ` or
+/// `1. `, is escaped too, since the text often follows a list marker of its own and would otherwise
+/// start a nested list.
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '|' | '~' | '#' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' | '\r' => escaped.push(' '),
+            c => escaped.push(c),
+        }
+    }
+
+    let start = escaped.len() - escaped.trim_start().len();
+    let rest = &escaped[start..];
+    let digits = rest.chars().take_while(char::is_ascii_digit).count();
+    if rest.starts_with(['-', '+']) {
+        escaped.insert(start, '\\');
+    } else if digits > 0 && rest[digits..].starts_with(['.', ')']) {
+        escaped.insert(start + digits, '\\');
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::room_state::{
+        ballot::Ballot,
+        test_support::{join, room},
+    };
+
+    /// The export of a room with three options, one of them vetoed, after its host and Ann both
+    /// voted with `ballots`, which gets the two remaining options' ids
+    fn export(tally_method: TallyMethodKind, ballots: fn(&[Uuid]) -> [Ballot; 2]) -> ResultsExport {
+        let settings = RoomSettings {
+            tally_method,
+            ..RoomSettings::default()
+        };
+        let (mut room, host, ids) = room(&["Tacos", "Pizza", "Sushi"], settings);
+        let ann = join(&mut room, "Ann");
+        room.veto(ann, ids[2], "Too pricey").unwrap();
+        room.finish_vetoing(host).unwrap();
+        let [host_ballot, ann_ballot] = ballots(&ids[..2]);
+        room.contribute_ballot(host, host_ballot).unwrap();
+        room.contribute_ballot(ann, ann_ballot).unwrap();
+        room.close_voting(host).unwrap();
+        ResultsExport::from(&room)
+    }
+
+    fn ranked_export() -> ResultsExport {
+        export(TallyMethodKind::Borda, |ids| {
+            [
+                Ballot::Ranked(vec![ids[0], ids[1]]),
+                Ballot::Ranked(vec![ids[1], ids[0]]),
+            ]
+        })
+    }
+
+    fn approval_export() -> ResultsExport {
+        export(TallyMethodKind::Approval, |ids| {
+            [
+                Ballot::Approval(vec![ids[0]]),
+                Ballot::Approval(vec![ids[0], ids[1]]),
+            ]
+        })
+    }
+
+    fn score_export() -> ResultsExport {
+        export(TallyMethodKind::Star, |ids| {
+            [
+                Ballot::Score(vec![(ids[0], 5), (ids[1], 2)]),
+                Ballot::Score(vec![(ids[0], 3), (ids[1], 4)]),
+            ]
+        })
+    }
+
+    #[test]
+    fn ranked_csv_export() {
+        assert_eq!(
+            ranked_export().to_csv(),
+            "\
+Room,test\n\
+Status,Final\n\
+Voting method,Borda count\n\
+Tie-break,Most first-place votes\n\
+Partial ballots,Unranked options tie for last\n\
+Veto rules,Each voter can veto any number of options. One veto is enough to strike an option.\n\
+Participants,\"Host, Ann\"\n\
+Ballots,2\n\
+\n\
+Place,Option,Score,Ranks\n\
+1,Tacos,3,\"1st, 2nd\"\n\
+1,Pizza,3,\"1st, 2nd\"\n\
+\"Tie between Tacos, Pizza left unbroken by most first-place votes\"\n\
+\n\
+Voter,Position,Choice\n\
+Host,1,Tacos\n\
+Host,2,Pizza\n\
+Ann,1,Pizza\n\
+Ann,2,Tacos\n\
+\n\
+Option,Vetoed by,Reason,Struck\n\
+Sushi,Ann,Too pricey,yes\n"
+        );
+    }
+
+    #[test]
+    fn ranked_markdown_export() {
+        assert_eq!(
+            ranked_export().to_markdown(),
+            "\
+# Results for room test\n\
+\n\
+- **Status:** Final\n\
+- **Voting method:** Borda count\n\
+- **Tie-break:** Most first-place votes\n\
+- **Partial ballots:** Unranked options tie for last\n\
+- **Veto rules:** Each voter can veto any number of options. One veto is enough to strike an option.\n\
+- **Participants:** Host, Ann\n\
+- **Ballots:** 2\n\
+\n\
+## Results\n\
+\n\
+| Place | Option | Score | Ranks |\n\
+| ---: | --- | ---: | --- |\n\
+| 1 | Tacos | 3 | 1st, 2nd |\n\
+| 1 | Pizza | 3 | 1st, 2nd |\n\
+\n\
+*Tie between Tacos, Pizza left unbroken by most first-place votes*\n\
+\n\
+## Ballots\n\
+\n\
+### Host\n\
+\n\
+1. Tacos\n\
+2. Pizza\n\
+\n\
+### Ann\n\
+\n\
+1. Pizza\n\
+2. Tacos\n\
+\n\
+## Vetoes\n\
+\n\
+- ~~Sushi~~: vetoed by Ann (\"Too pricey\")\n"
+        );
+    }
+
+    #[test]
+    fn approval_csv_export() {
+        assert_eq!(
+            approval_export().to_csv(),
+            "\
+Room,test\n\
+Status,Final\n\
+Voting method,Approval\n\
+Tie-break,Most first-place votes\n\
+Veto rules,Each voter can veto any number of options. One veto is enough to strike an option.\n\
+Participants,\"Host, Ann\"\n\
+Ballots,2\n\
+\n\
+Place,Option,Score,Ranks\n\
+1,Tacos,2,\n\
+2,Pizza,1,\n\
+\n\
+Voter,Choice\n\
+Host,Tacos\n\
+Ann,Tacos\n\
+Ann,Pizza\n\
+\n\
+Option,Vetoed by,Reason,Struck\n\
+Sushi,Ann,Too pricey,yes\n"
+        );
+    }
+
+    #[test]
+    fn approval_markdown_export() {
+        assert_eq!(
+            approval_export().to_markdown(),
+            "\
+# Results for room test\n\
+\n\
+- **Status:** Final\n\
+- **Voting method:** Approval\n\
+- **Tie-break:** Most first-place votes\n\
+- **Veto rules:** Each voter can veto any number of options. One veto is enough to strike an option.\n\
+- **Participants:** Host, Ann\n\
+- **Ballots:** 2\n\
+\n\
+## Results\n\
+\n\
+| Place | Option | Score | Ranks |\n\
+| ---: | --- | ---: | --- |\n\
+| 1 | Tacos | 2 |  |\n\
+| 2 | Pizza | 1 |  |\n\
+\n\
+## Ballots\n\
+\n\
+### Host\n\
+\n\
+- Tacos\n\
+\n\
+### Ann\n\
+\n\
+- Tacos\n\
+- Pizza\n\
+\n\
+## Vetoes\n\
+\n\
+- ~~Sushi~~: vetoed by Ann (\"Too pricey\")\n"
+        );
+    }
+
+    #[test]
+    fn score_csv_export() {
+        assert_eq!(
+            score_export().to_csv(),
+            "\
+Room,test\n\
+Status,Final\n\
+Voting method,\"STAR (score, then automatic runoff)\"\n\
+Tie-break,Most first-place votes\n\
+Veto rules,Each voter can veto any number of options. One veto is enough to strike an option.\n\
+Participants,\"Host, Ann\"\n\
+Ballots,2\n\
+\n\
+Place,Option,Score,Ranks\n\
+1,Tacos,8,\n\
+2,Pizza,6,\n\
+\n\
+Voter,Choice\n\
+Host,Tacos (5/5)\n\
+Host,Pizza (2/5)\n\
+Ann,Pizza (4/5)\n\
+Ann,Tacos (3/5)\n\
+\n\
+Option,Vetoed by,Reason,Struck\n\
+Sushi,Ann,Too pricey,yes\n"
+        );
+    }
+
+    #[test]
+    fn score_markdown_export() {
+        assert_eq!(
+            score_export().to_markdown(),
+            "\
+# Results for room test\n\
+\n\
+- **Status:** Final\n\
+- **Voting method:** STAR (score, then automatic runoff)\n\
+- **Tie-break:** Most first-place votes\n\
+- **Veto rules:** Each voter can veto any number of options. One veto is enough to strike an option.\n\
+- **Participants:** Host, Ann\n\
+- **Ballots:** 2\n\
+\n\
+## Results\n\
+\n\
+| Place | Option | Score | Ranks |\n\
+| ---: | --- | ---: | --- |\n\
+| 1 | Tacos | 8 |  |\n\
+| 2 | Pizza | 6 |  |\n\
+\n\
+## Ballots\n\
+\n\
+### Host\n\
+\n\
+- Tacos (5/5)\n\
+- Pizza (2/5)\n\
+\n\
+### Ann\n\
+\n\
+- Pizza (4/5)\n\
+- Tacos (3/5)\n\
+\n\
+## Vetoes\n\
+\n\
+- ~~Sushi~~: vetoed by Ann (\"Too pricey\")\n"
+        );
+    }
+
+    #[test]
+    fn plain_csv_fields_are_left_alone() {
+        assert_eq!(escape_csv("Tacos"), "Tacos");
+        assert_eq!(escape_csv(""), "");
+    }
+
+    #[test]
+    fn csv_fields_that_would_split_are_quoted() {
+        assert_eq!(escape_csv("Tacos, obviously"), "\"Tacos, obviously\"");
+        assert_eq!(
+            escape_csv("The \"good\" place"),
+            "\"The \"\"good\"\" place\""
+        );
+        assert_eq!(escape_csv("two\nlines"), "\"two\nlines\"");
+    }
+
+    #[test]
+    fn csv_fields_that_look_like_formulas_are_kept_as_text() {
+        assert_eq!(escape_csv("=1+1"), "'=1+1");
+        assert_eq!(escape_csv("+1"), "'+1");
+        assert_eq!(escape_csv("-1"), "'-1");
+        assert_eq!(escape_csv("@SUM(A1)"), "'@SUM(A1)");
+        assert_eq!(escape_csv("\tTacos"), "'\tTacos");
+        assert_eq!(
+            escape_csv("=HYPERLINK(\"http://example.com\", \"Tacos\")"),
+            "\"'=HYPERLINK(\"\"http://example.com\"\", \"\"Tacos\"\")\""
+        );
+        assert_eq!(escape_csv("\rTacos"), "\"'\rTacos\"");
+        assert_eq!(escape_csv("Tacos = good"), "Tacos = good");
+    }
+
+    #[test]
+    fn markdown_formatting_is_escaped() {
+        assert_eq!(escape_markdown("Tacos"), "Tacos");
+        assert_eq!(escape_markdown("*Tacos*"), "\\*Tacos\\*");
+        assert_eq!(
+            escape_markdown("[Tacos](http://example.com)"),
+            "\\[Tacos\\](http://example.com)"
+        );
+        assert_eq!(escape_markdown("a | b"), "a \\| b");
+        assert_eq!(escape_markdown("# not a heading"), "\\# not a heading");
+        assert_eq!(escape_markdown("back\\slash"), "back\\\\slash");
+        assert_eq!(escape_markdown("<b>_~`"), "\\<b\\>\\_\\~\\`");
+    }
+
+    #[test]
+    fn markdown_list_markers_at_the_start_are_escaped() {
+        assert_eq!(escape_markdown("- Tacos"), "\\- Tacos");
+        assert_eq!(escape_markdown("+ Tacos"), "\\+ Tacos");
+        assert_eq!(escape_markdown("1. Tacos"), "1\\. Tacos");
+        assert_eq!(escape_markdown("12) Tacos"), "12\\) Tacos");
+        assert_eq!(escape_markdown("  - Tacos"), "  \\- Tacos");
+        assert_eq!(escape_markdown("Tacos - 2. go"), "Tacos - 2. go");
+        assert_eq!(escape_markdown("2024 tacos"), "2024 tacos");
+    }
+
+    #[test]
+    fn markdown_is_kept_on_one_line() {
+        assert_eq!(escape_markdown("two\nlines"), "two lines");
+        assert_eq!(escape_markdown("two\r\nlines"), "two  lines");
+    }
+}
@@ -1,56 +1,175 @@
 use app::App;
-use axum::{extract::Path, response::IntoResponse, routing::get, Extension, Router};
+use axum::{extract::Path, middleware, response::IntoResponse, routing::get, Extension, Router};
 use axum_live_view::{html, LiveViewUpgrade};
+use config::Config;
 use pages::{room_choice_page::RoomChoicePage, AppPage};
-use server_state::ServerState;
+use room_state::participant::ParticipantId;
+use serde::Serialize;
+use server_state::{spawn_room_reaper, ServerState};
+use session::{Sessions, SharedSessions};
 use std::sync::{Arc, RwLock};
+use storage::writer::StorageWriter;
 use tokio::sync::broadcast;
 use tower::ServiceBuilder;
 use tower_http::add_extension::AddExtensionLayer;
 use tracing::info;
 use tracing_panic::panic_hook;
+use uuid::Uuid;
 
+pub mod api;
 pub mod app;
+pub mod config;
+pub mod export;
 pub mod pages;
 pub mod room_state;
 pub mod server_state;
+pub mod session;
+pub mod storage;
 
 pub type ServerwideSharedState = Arc<RwLock<ServerState>>;
 pub type BroadcastSender = broadcast::Sender<BroadcastMsg>;
 pub type BroadcastReceiver = broadcast::Receiver<BroadcastMsg>;
-pub type BroadcastReceiverSender = std::sync::mpsc::Sender<broadcast::Receiver<BroadcastMsg>>;
+pub type BroadcastReceiverSender =
+    tokio::sync::mpsc::UnboundedSender<broadcast::Receiver<BroadcastMsg>>;
 
+#[cfg(feature = "shuttle")]
 #[shuttle_runtime::main]
 async fn axum() -> shuttle_axum::ShuttleAxum {
     std::panic::set_hook(Box::new(panic_hook));
     info!("Starting server!");
 
-    let app = Router::new()
+    let app = build_app(&Config::from_env())?;
+    Ok(app.into())
+}
+
+#[cfg(not(feature = "shuttle"))]
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    use clap::Parser;
+    use std::net::SocketAddr;
+
+    tracing_subscriber::fmt::init();
+    std::panic::set_hook(Box::new(panic_hook));
+    let config = Config::parse();
+    info!("Starting server!");
+
+    let app = build_app(&config)?;
+    let address = SocketAddr::new(config.bind_address, config.port);
+    info!("Listening on {}", address);
+    axum::Server::bind(&address)
+        .serve(app.into_make_service())
+        .await?;
+
+    Ok(())
+}
+
+/// Builds the app's routes, loading saved rooms and sessions and starting the idle room reaper along
+/// the way
+fn build_app(config: &Config) -> anyhow::Result<Router> {
+    let storage = config.storage()?;
+    let writer = StorageWriter::spawn(storage.clone());
+    let state = ServerwideSharedState::new(RwLock::new(ServerState::load(
+        storage.as_ref(),
+        writer.clone(),
+    )?));
+    let sessions = SharedSessions::new(Sessions::load(storage.as_ref(), writer)?);
+    spawn_room_reaper(state.clone(), sessions.clone(), config.room_ttl());
+
+    Ok(Router::new()
         .route("/", get(root))
         .route("/room/:room_code", get(room))
         .route("/room/:room_code/results", get(room_results))
+        .merge(api::router())
+        // Only the routes above need to know who's asking, so assets, exports, event streams and
+        // health checks don't start a session for every visitor
+        .route_layer(middleware::from_fn(session::session_cookie))
+        .route("/room/:room_code/results.json", get(export::results_json))
+        .route("/room/:room_code/results.csv", get(export::results_csv))
+        .route("/room/:room_code/results.md", get(export::results_markdown))
         .route("/assets/live-view.js", axum_live_view::precompiled_js())
+        .merge(api::public_router())
         .layer(
             ServiceBuilder::new()
-                .layer(AddExtensionLayer::new(ServerwideSharedState::default()))
+                .layer(AddExtensionLayer::new(state))
+                .layer(AddExtensionLayer::new(sessions))
                 .into_inner(),
-        );
+        ))
+}
 
-    Ok(app.into())
+/// Something that happened in a room, sent to everyone watching it
+#[derive(Clone, Debug, Serialize)]
+pub struct BroadcastMsg {
+    /// Counts up by one with each of the room's events, so listeners can tell when they missed some
+    pub seq: u64,
+    pub event: RoomEvent,
 }
 
-#[derive(Clone, Copy, Debug)]
-pub enum BroadcastMsg {
-    UpdatedVetos,
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "type")]
+pub enum RoomEvent {
+    ParticipantJoined {
+        participant: ParticipantId,
+    },
+    PresenceChanged {
+        participant: ParticipantId,
+        online: bool,
+    },
+    OptionAdded {
+        option: Uuid,
+        by: ParticipantId,
+    },
+    OptionRenamed {
+        option: Uuid,
+        by: ParticipantId,
+    },
+    OptionRemoved {
+        option: Uuid,
+        by: ParticipantId,
+    },
+    OptionVetoed {
+        option: Uuid,
+        by: ParticipantId,
+    },
+    VetoUndone {
+        option: Uuid,
+        vetoer: ParticipantId,
+        by: ParticipantId,
+    },
+    VetosReset {
+        by: ParticipantId,
+    },
+    ReadyChanged {
+        participant: ParticipantId,
+        ready: bool,
+    },
     FinishedVetoing,
-    UpdatedVotes,
+    BallotSubmitted {
+        voter: ParticipantId,
+    },
+    HostChanged {
+        host: ParticipantId,
+    },
+    LockChanged {
+        locked: bool,
+    },
+    ParticipantKicked {
+        participant: ParticipantId,
+    },
+    VotingClosed,
+    RoomClosed,
 }
 
 async fn root(
     live: LiveViewUpgrade,
     Extension(state): Extension<ServerwideSharedState>,
+    Extension(participant): Extension<ParticipantId>,
 ) -> impl IntoResponse {
-    let app = App::new(state, Box::new(RoomChoicePage::default()), None);
+    let app = App::new(
+        state,
+        Box::new(RoomChoicePage::new(participant, None)),
+        None,
+        participant,
+    );
     live_view_response(live, app)
 }
 
@@ -58,10 +177,16 @@ async fn room(
     Path(room_code): Path<String>,
     live: LiveViewUpgrade,
     Extension(state): Extension<ServerwideSharedState>,
+    Extension(participant): Extension<ParticipantId>,
 ) -> impl IntoResponse {
     live_view_response(
         live,
-        get_app_starting_on_room_page(room_code, state, ServerState::get_room_voting_page),
+        get_app_starting_on_room_page(
+            room_code,
+            state,
+            participant,
+            ServerState::get_room_voting_page,
+        ),
     )
 }
 
@@ -69,26 +194,35 @@ async fn room_results(
     Path(room_code): Path<String>,
     live: LiveViewUpgrade,
     Extension(state): Extension<ServerwideSharedState>,
+    Extension(participant): Extension<ParticipantId>,
 ) -> impl IntoResponse {
     live_view_response(
         live,
-        get_app_starting_on_room_page(room_code, state, ServerState::get_room_results_page),
+        get_app_starting_on_room_page(
+            room_code,
+            state,
+            participant,
+            ServerState::get_room_results_page,
+        ),
     )
 }
 
 fn get_app_starting_on_room_page(
     room_code: String,
     state: ServerwideSharedState,
+    participant: ParticipantId,
     get_room_page: impl FnOnce(
         &ServerState,
         &str,
+        ParticipantId,
     )
         -> Result<(Box<dyn AppPage + Send + Sync>, BroadcastReceiver), String>,
 ) -> App {
-    let (starting_page, broadcast_rx) = get_room_page(&state.read().unwrap(), &room_code)
-        .map(|(page, rx)| (page, Some(rx)))
-        .unwrap_or_else(|e| (Box::new(RoomChoicePage::new(Some(e))), None));
-    App::new(state, starting_page, broadcast_rx)
+    let (starting_page, broadcast_rx) =
+        get_room_page(&state.read().unwrap(), &room_code, participant)
+            .map(|(page, rx)| (page, Some(rx)))
+            .unwrap_or_else(|e| (Box::new(RoomChoicePage::new(participant, Some(e))), None));
+    App::new(state, starting_page, broadcast_rx, participant)
 }
 
 fn live_view_response(live: LiveViewUpgrade, app: App) -> impl IntoResponse {
@@ -0,0 +1,123 @@
+use crate::{
+    app::AppMsg,
+    room_state::{ballot::Ballot, participant::ParticipantId, RoomState},
+    BroadcastSender, ServerwideSharedState,
+};
+use axum_live_view::html;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+
+use super::{
+    deserialize_form, get_results_page_response, roster::render_roster, AppPage, AppUpdateResponse,
+};
+
+pub struct ApprovalPage {
+    pub room_code: String,
+    pub room_state: Arc<RwLock<RoomState>>,
+    pub broadcast_tx: BroadcastSender,
+    participant: ParticipantId,
+}
+
+impl ApprovalPage {
+    pub fn new(
+        room_code: String,
+        room_state: Arc<RwLock<RoomState>>,
+        broadcast_tx: BroadcastSender,
+        participant: ParticipantId,
+    ) -> Self {
+        Self {
+            room_code,
+            room_state,
+            broadcast_tx,
+            participant,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub enum ApprovalMsg {
+    SubmitApprovals,
+    JustViewResults,
+}
+
+impl AppPage for ApprovalPage {
+    fn update(
+        &mut self,
+        msg: AppMsg,
+        data: Option<axum_live_view::event_data::EventData>,
+        _server_shared_state: &mut ServerwideSharedState,
+        _broadcast_rx_tx: &mut crate::BroadcastReceiverSender,
+    ) -> anyhow::Result<AppUpdateResponse> {
+        if let AppMsg::ApprovalMsg(msg) = msg {
+            match msg {
+                ApprovalMsg::SubmitApprovals => {
+                    // Only ticked checkboxes are submitted, keyed by option id
+                    let ticked = deserialize_form::<HashMap<String, String>>(data)?;
+                    {
+                        let mut room_state = self.room_state.write().unwrap();
+                        let approved = room_state
+                            .iter_options()
+                            .filter(|o| {
+                                !o.vetoed && ticked.contains_key(&o.id.as_simple().to_string())
+                            })
+                            .map(|o| o.id)
+                            .collect();
+                        room_state
+                            .contribute_ballot(self.participant, Ballot::Approval(approved))?;
+                    }
+                    return get_results_page_response(
+                        &self.room_code,
+                        &self.room_state,
+                        self.participant,
+                    );
+                }
+                ApprovalMsg::JustViewResults => {
+                    return get_results_page_response(
+                        &self.room_code,
+                        &self.room_state,
+                        self.participant,
+                    );
+                }
+            }
+        }
+
+        Ok((None, None).into())
+    }
+
+    fn render(&self) -> axum_live_view::Html<crate::app::AppMsg> {
+        let room_state = self.room_state.read().unwrap();
+        let previously_approved = room_state
+            .get_ballot(&self.participant)
+            .map_or(Vec::new(), |ballot| ballot.option_ids());
+        html! {
+            <div>
+                <h1>"Now, approve!"</h1>
+                <h3>{format!("Room: {}", self.room_code)}</h3>
+                <p>"Tick every option you'd be happy to do. The option approved by the most voters wins."</p>
+                <button style="font-size:0.75rem;" axm-click={AppMsg::ApprovalMsg(ApprovalMsg::JustViewResults)}>"View Results w/o Voting"</button>
+                <form axm-submit={ AppMsg::ApprovalMsg(ApprovalMsg::SubmitApprovals) }>
+                    for option in room_state.iter_options().filter(|o| !o.vetoed) {
+                        <p>
+                            if previously_approved.contains(&option.id) {
+                                <input type="checkbox" checked id={option.id.as_simple().to_string()} name={option.id.as_simple().to_string()}/>
+                            } else {
+                                <input type="checkbox" id={option.id.as_simple().to_string()} name={option.id.as_simple().to_string()}/>
+                            }
+                            <label for={option.id.as_simple().to_string()}>{option.get_html_text()}</label>
+                        </p>
+                    }
+
+                    <input type="submit" value="Submit Approvals"/>
+                </form>
+                {render_roster(&room_state, self.participant)}
+            </div>
+        }
+    }
+
+    fn room_state(&self) -> Option<&Arc<RwLock<RoomState>>> {
+        Some(&self.room_state)
+    }
+}
@@ -0,0 +1,113 @@
+use super::{deserialize_form, get_voting_stage_page, AppPage, AppUpdateResponse};
+use crate::{
+    app::AppMsg,
+    room_state::{
+        participant::{ParticipantId, MAX_NICKNAME_LENGTH},
+        RoomState,
+    },
+    BroadcastSender,
+};
+use axum_live_view::html;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, RwLock};
+
+/// Asks for a nickname before letting someone into a room they haven't joined yet
+pub struct JoinPage {
+    pub room_code: String,
+    pub room_state: Arc<RwLock<RoomState>>,
+    broadcast_tx: BroadcastSender,
+    participant: ParticipantId,
+    join_error_msg: Option<String>,
+}
+
+impl JoinPage {
+    pub fn new(
+        room_code: String,
+        room_state: Arc<RwLock<RoomState>>,
+        broadcast_tx: BroadcastSender,
+        participant: ParticipantId,
+    ) -> Self {
+        Self {
+            room_code,
+            room_state,
+            broadcast_tx,
+            participant,
+            join_error_msg: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub enum JoinMsg {
+    Join,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct JoinFormSubmit {
+    nickname: String,
+}
+
+impl AppPage for JoinPage {
+    fn update(
+        &mut self,
+        msg: AppMsg,
+        data: Option<axum_live_view::event_data::EventData>,
+        _server_shared_state: &mut crate::ServerwideSharedState,
+        _broadcast_rx_tx: &mut crate::BroadcastReceiverSender,
+    ) -> anyhow::Result<AppUpdateResponse> {
+        if let AppMsg::JoinMsg(msg) = msg {
+            match msg {
+                JoinMsg::Join => {
+                    let nickname = deserialize_form::<JoinFormSubmit>(data)?.nickname;
+                    let joined = self
+                        .room_state
+                        .write()
+                        .unwrap()
+                        .join(self.participant, &nickname);
+                    match joined {
+                        Ok(()) => {
+                            return Ok((
+                                Some(get_voting_stage_page(
+                                    self.room_code.clone(),
+                                    self.room_state.clone(),
+                                    self.broadcast_tx.clone(),
+                                    self.participant,
+                                )),
+                                None,
+                            )
+                                .into());
+                        }
+                        Err(e) => self.join_error_msg = Some(e.to_string()),
+                    }
+                }
+            }
+        }
+
+        Ok((None, None).into())
+    }
+
+    fn render(&self) -> axum_live_view::Html<AppMsg> {
+        html! {
+            <div>
+                <h1>"Who are you?"</h1>
+                <h3>{format!("Room: {}", self.room_code)}</h3>
+                <p>"Pick a nickname so everyone knows whose vetoes, options and votes are whose."</p>
+                <form axm-submit={ AppMsg::JoinMsg(JoinMsg::Join) }>
+                    <input
+                        type="text"
+                        name="nickname"
+                        maxlength={MAX_NICKNAME_LENGTH.to_string()}
+                        placeholder="Nickname"
+                    />
+
+                    <input type="submit" value="Join"/>
+                </form>
+                <p>{ammonia::clean_text(&self.join_error_msg.as_ref().map_or("".to_string(), |s| format!("Error: {}", s)))}</p>
+            </div>
+        }
+    }
+
+    fn room_state(&self) -> Option<&Arc<RwLock<RoomState>>> {
+        Some(&self.room_state)
+    }
+}
@@ -1,12 +1,27 @@
-use crate::{app::AppMsg, ServerwideSharedState};
+use crate::{
+    app::AppMsg,
+    room_state::{participant::ParticipantId, RoomState, VotingStage},
+    BroadcastSender, ServerwideSharedState,
+};
 use anyhow::anyhow;
 use anyhow::Result;
-use axum_live_view::{event_data::EventData, Html};
+use axum_live_view::{event_data::EventData, js_command, Html};
+use std::sync::{Arc, RwLock};
 
+use self::{
+    approval_page::ApprovalPage, join_page::JoinPage, ranking_page::RankingPage,
+    results_page::ResultsPage, score_page::ScorePage, veto_page::VetoPage,
+};
+
+pub mod approval_page;
 pub mod error_page;
+pub mod join_page;
+pub mod option_details;
 pub mod ranking_page;
 pub mod results_page;
 pub mod room_choice_page;
+pub mod roster;
+pub mod score_page;
 pub mod veto_page;
 
 pub trait AppPage {
@@ -18,6 +33,10 @@ pub trait AppPage {
         broadcast_rx_tx: &mut crate::BroadcastReceiverSender,
     ) -> Result<AppUpdateResponse>;
     fn render(&self) -> Html<AppMsg>;
+    /// The room this page belongs to, if any. Used to track who's present in which room.
+    fn room_state(&self) -> Option<&Arc<RwLock<RoomState>>> {
+        None
+    }
 }
 
 pub struct AppUpdateResponse {
@@ -54,3 +73,81 @@ where
         .ok_or(anyhow!("Event data was not a form"))?
         .deserialize::<T>()?)
 }
+
+/// Gets the page for whichever stage of voting the room is currently in
+pub fn get_voting_stage_page(
+    room_code: String,
+    room_state: Arc<RwLock<RoomState>>,
+    broadcast_tx: BroadcastSender,
+    participant: ParticipantId,
+) -> Box<dyn AppPage + Send + Sync> {
+    let voting_stage = room_state.read().unwrap().voting_stage();
+    match voting_stage {
+        VotingStage::Vetoing => Box::new(VetoPage::new(
+            room_code,
+            room_state,
+            broadcast_tx,
+            participant,
+        )),
+        VotingStage::Ranking => Box::new(RankingPage::new(
+            room_code,
+            room_state,
+            broadcast_tx,
+            participant,
+        )),
+        VotingStage::Approving => Box::new(ApprovalPage::new(
+            room_code,
+            room_state,
+            broadcast_tx,
+            participant,
+        )),
+        VotingStage::Scoring => Box::new(ScorePage::new(
+            room_code,
+            room_state,
+            broadcast_tx,
+            participant,
+        )),
+        VotingStage::Closed => Box::new(ResultsPage {
+            room_code,
+            room_state,
+            participant,
+        }),
+    }
+}
+
+/// Like `get_voting_stage_page`, but asks for a nickname first if the participant hasn't joined the room
+pub fn get_room_entry_page(
+    room_code: String,
+    room_state: Arc<RwLock<RoomState>>,
+    broadcast_tx: BroadcastSender,
+    participant: ParticipantId,
+) -> Box<dyn AppPage + Send + Sync> {
+    let joined = room_state.read().unwrap().is_participant(&participant);
+    if joined {
+        get_voting_stage_page(room_code, room_state, broadcast_tx, participant)
+    } else {
+        Box::new(JoinPage::new(
+            room_code,
+            room_state,
+            broadcast_tx,
+            participant,
+        ))
+    }
+}
+
+pub fn get_results_page_response(
+    room_code: &str,
+    room_state: &Arc<RwLock<RoomState>>,
+    participant: ParticipantId,
+) -> Result<AppUpdateResponse> {
+    Ok(AppUpdateResponse {
+        next_page: Some(Box::new(ResultsPage {
+            room_code: room_code.to_string(),
+            room_state: room_state.clone(),
+            participant,
+        })),
+        js_commands: Some(vec![js_command::history_push_state(
+            format!("/room/{}/results", room_code).parse().unwrap(),
+        )]),
+    })
+}
@@ -0,0 +1,30 @@
+use axum_live_view::{html, Html};
+
+use crate::{app::AppMsg, room_state::option_details::OptionDetails};
+
+const IMAGE_STYLE: &str = "display:block;max-width:12rem;max-height:8rem;margin:4px 0;";
+
+/// Shows an option's description, link and image beneath its text, if it has any
+pub fn render_option_details(details: &OptionDetails) -> Html<AppMsg> {
+    // Plain `if`s rather than `if let Some ...`, which html! mishandles when the page changes
+    let has_image = details.image_url().is_some();
+    let image_url = details.get_html_image_url().unwrap_or_default();
+    let has_description = details.description().is_some();
+    let description = details.get_html_description().unwrap_or_default();
+    let has_url = details.url().is_some();
+    let url = details.get_html_url().unwrap_or_default();
+    html! {
+        <div style="font-size:0.85rem;">
+            if has_image {
+                <img src={image_url} alt="" style={IMAGE_STYLE}/>
+            }
+            if has_description {
+                <span>{description}</span>
+                " "
+            }
+            if has_url {
+                <a href={url} target="_blank" rel="noopener noreferrer">"Link"</a>
+            }
+        </div>
+    }
+}
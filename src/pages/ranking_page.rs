@@ -1,16 +1,22 @@
 use crate::{
-    app::AppMsg, room_state::RoomState, BroadcastMsg, BroadcastSender, ServerwideSharedState,
+    app::AppMsg,
+    room_state::{participant::ParticipantId, RoomState},
+    BroadcastSender, ServerwideSharedState,
 };
-use axum_live_view::{html, js_command};
+use axum_live_view::html;
 use serde::{Deserialize, Serialize};
 use std::sync::{Arc, RwLock};
 
-use super::{deserialize_form, results_page::ResultsPage, AppPage, AppUpdateResponse};
+use super::{
+    deserialize_form, get_results_page_response, option_details::render_option_details,
+    roster::render_roster, AppPage, AppUpdateResponse,
+};
 
 pub struct RankingPage {
     pub room_code: String,
     pub room_state: Arc<RwLock<RoomState>>,
     pub broadcast_tx: BroadcastSender,
+    participant: ParticipantId,
 }
 
 impl RankingPage {
@@ -18,25 +24,15 @@ impl RankingPage {
         room_code: String,
         room_state: Arc<RwLock<RoomState>>,
         broadcast_tx: BroadcastSender,
+        participant: ParticipantId,
     ) -> Self {
         Self {
             room_code,
             room_state,
             broadcast_tx,
+            participant,
         }
     }
-
-    fn get_results_page_response(&mut self) -> anyhow::Result<AppUpdateResponse> {
-        Ok(AppUpdateResponse {
-            next_page: Some(Box::new(ResultsPage {
-                room_code: self.room_code.clone(),
-                room_state: self.room_state.clone(),
-            })),
-            js_commands: Some(vec![js_command::history_push_state(
-                format!("/room/{}/results", self.room_code).parse().unwrap(),
-            )]),
-        })
-    }
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
@@ -66,12 +62,19 @@ impl AppPage for RankingPage {
                     self.room_state
                         .write()
                         .unwrap()
-                        .contribute_votes(ranked_options);
-                    self.broadcast_tx.send(BroadcastMsg::UpdatedVotes)?;
-                    return self.get_results_page_response();
+                        .contribute_votes(self.participant, ranked_options)?;
+                    return get_results_page_response(
+                        &self.room_code,
+                        &self.room_state,
+                        self.participant,
+                    );
                 }
                 RankingMsg::JustViewResults => {
-                    return self.get_results_page_response();
+                    return get_results_page_response(
+                        &self.room_code,
+                        &self.room_state,
+                        self.participant,
+                    );
                 }
             }
         }
@@ -81,16 +84,20 @@ impl AppPage for RankingPage {
 
     fn render(&self) -> axum_live_view::Html<crate::app::AppMsg> {
         let room_state = self.room_state.read().unwrap();
+        let has_voted = room_state.has_voted(&self.participant);
         html! {
             <div>
                 <h1>"Now, rank!"</h1>
                 <h3>{format!("Room: {}", self.room_code)}</h3>
                 <p>"Each voter can rank their preferences individually by dragging and dropping the items in the list below."</p>
+                if has_voted {
+                    <p>"The list is in the order of your last ranking. Submitting again replaces it."</p>
+                }
                 <button style="font-size:0.75rem;" axm-click={AppMsg::RankingMsg(RankingMsg::JustViewResults)}>"View Results w/o Voting"</button>
                 <div>
                     <ol id="sortableList">
-                        for option in room_state.iter_options().filter(|o| !o.vetoed) {
-                            <li style="cursor:move;" option-id={option.id.as_simple().to_string()}>{option.get_html_text()}</li> // Can't use data-id here how SortableJS wants you too, doing so produces "unreachable!("unable to find a way to hit this yolo")" in diff.rs
+                        for option in room_state.get_options_in_ranking_order(&self.participant) {
+                            <li style="cursor:move;" option-id={option.id.as_simple().to_string()}>{option.get_html_text()}{render_option_details(option.details())}</li> // Can't use data-id here how SortableJS wants you too, doing so produces "unreachable!("unable to find a way to hit this yolo")" in diff.rs
                         }
                     </ol>
 
@@ -104,7 +111,12 @@ impl AppPage for RankingPage {
                         <input type="submit" value="Submit Ranking"/>
                     </form>
                 </div>
+                {render_roster(&room_state, self.participant)}
             </div>
         }
     }
+
+    fn room_state(&self) -> Option<&Arc<RwLock<RoomState>>> {
+        Some(&self.room_state)
+    }
 }
@@ -1,24 +1,39 @@
-use axum_live_view::html;
+use axum_live_view::{html, js_command, Html};
 use itertools::Itertools;
 use ordinal::Ordinal;
 use serde::{Deserialize, Serialize};
 
 use crate::{
     app::AppMsg,
-    room_state::{FinalVoteTally, RoomState},
+    room_state::{
+        ballot::BallotKind,
+        option_details::OptionDetails,
+        participant::ParticipantId,
+        tally::{EliminationTieBreak, PairwiseMatrix, RunoffRound, StarRunoff, TallyBreakdown},
+        tie_break::{TieBreak, TieBreakPolicy},
+        FinalVoteTally, RoomState,
+    },
 };
 use std::sync::{Arc, RwLock};
+use uuid::Uuid;
 
-use super::{AppPage, AppUpdateResponse};
+use super::{
+    get_room_entry_page, option_details::render_option_details, roster::render_roster, AppPage,
+    AppUpdateResponse,
+};
+
+const EXHAUSTED_TEXT: &str = "no remaining choice (exhausted)";
 
 pub struct ResultsPage {
     pub room_code: String,
     pub room_state: Arc<RwLock<RoomState>>,
+    pub participant: ParticipantId,
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
 pub enum ResultsMsg {
     ResultsUpdated,
+    ChangeVote,
 }
 
 impl AppPage for ResultsPage {
@@ -32,6 +47,21 @@ impl AppPage for ResultsPage {
         if let AppMsg::ResultsMsg(msg) = msg {
             match msg {
                 ResultsMsg::ResultsUpdated => (), // re-render
+                ResultsMsg::ChangeVote => {
+                    let broadcast_tx = self.room_state.read().unwrap().get_broadcast_tx();
+                    return Ok((
+                        Some(get_room_entry_page(
+                            self.room_code.clone(),
+                            self.room_state.clone(),
+                            broadcast_tx,
+                            self.participant,
+                        )),
+                        Some(vec![js_command::history_push_state(
+                            format!("/room/{}", self.room_code).parse().unwrap(),
+                        )]),
+                    )
+                        .into());
+                }
             }
         }
 
@@ -40,30 +70,67 @@ impl AppPage for ResultsPage {
 
     fn render(&self) -> axum_live_view::Html<crate::app::AppMsg> {
         let room_state = self.room_state.read().unwrap();
-        let tallies = room_state.tally_votes();
-        let highest_score = tallies.first().map_or(0, |t| t.score);
+        let outcome = room_state.tally_votes();
+        let tallies = outcome.tallies;
+        let is_ranked = room_state.settings().tally_method.ballot_kind() == BallotKind::Ranked;
+        let tie_break_texts = outcome
+            .tie_breaks
+            .iter()
+            .map(|tie_break| get_tie_break_text(tie_break, &tallies, &room_state))
+            .collect::<Vec<_>>();
+        let can_vote = room_state.is_voting_open();
+        let has_vetoes = room_state.iter_options().any(|o| !o.vetoes.is_empty());
+        let results_path = format!("/room/{}/results", self.room_code);
+        let change_vote_text = if room_state.has_voted(&self.participant) {
+            "Change my vote"
+        } else {
+            "Vote"
+        };
         html! {
             <div>
                 <h1>"Results"</h1>
                 <h3>{format!("Room: {}", self.room_code)}</h3>
-                <p>"A score is calculated for each option, weighted by the rankings it received from each voter."</p>
+                if can_vote {
+                    <button style="font-size:0.75rem;" axm-click={AppMsg::ResultsMsg(ResultsMsg::ChangeVote)}>{change_vote_text}</button>
+                }
+                <p>{room_state.settings().tally_method.description()}</p>
+                if is_ranked {
+                    <p>{room_state.settings().partial_ballots.description()}</p>
+                }
                 <h2>"Final Results"</h2>
                 <div>
                     <ol>
                         for tally in tallies.iter() {
-                            if tally.score == highest_score {
-                                <h3><li>{get_summary_text(&tally)}</li></h3>
+                            if tally.place == 1 {
+                                <h3><li>{get_summary_text(&tally)}{render_tally_details(&tally, &room_state)}</li></h3>
                             } else {
-                                <li>{get_summary_text(&tally)}</li>
+                                <li>{get_summary_text(&tally)}{render_tally_details(&tally, &room_state)}</li>
                             }
                         }
                     </ol>
+                    for text in tie_break_texts.iter() {
+                        <p><i>{text.clone()}</i></p>
+                    }
                 </div>
+                {render_breakdown(&outcome.breakdown, &room_state)}
+                if has_vetoes {
+                    <h4>"Vetoes"</h4>
+                    <ul>
+                        for option in room_state.iter_options().filter(|o| !o.vetoes.is_empty()) {
+                            if option.vetoed {
+                                <li><s>{option.get_html_text()}</s>{format!(": {}", room_state.get_html_vetoes_text(option))}</li>
+                            } else {
+                                <li>{option.get_html_text()}{format!(": {}", room_state.get_html_vetoes_text(option))}</li>
+                            }
+                        }
+                    </ul>
+                }
                 <h4>"All Votes"</h4>
                 <div>
                     <ul>
-                        for votes in room_state.iter_html_displayable_votes() {
+                        for (voter, votes) in room_state.iter_html_displayable_votes() {
                             <li>
+                                {get_voter_text(&voter, self.participant, &room_state)}
                                 <ol>
                                     for option in votes {
                                         <li>{option}</li>
@@ -74,18 +141,263 @@ impl AppPage for ResultsPage {
                         }
                     </ul>
                 </div>
+                <p style="font-size:0.85rem;">
+                    "Download: "
+                    <a href={format!("{}.json", results_path)} download>"JSON"</a>
+                    " | "
+                    <a href={format!("{}.csv", results_path)} download>"CSV"</a>
+                    " | "
+                    <a href={format!("{}.md", results_path)} download>"Markdown"</a>
+                </p>
+                {render_roster(&room_state, self.participant)}
+            </div>
+        }
+    }
+
+    fn room_state(&self) -> Option<&Arc<RwLock<RoomState>>> {
+        Some(&self.room_state)
+    }
+}
+
+fn render_breakdown(breakdown: &TallyBreakdown, room_state: &RoomState) -> Html<AppMsg> {
+    match breakdown {
+        TallyBreakdown::None => html! { <div></div> },
+        TallyBreakdown::InstantRunoff(rounds) => html! {
+            <div>
+                <h4>"Runoff Rounds"</h4>
+                <ol>
+                    for round in rounds.iter() {
+                        <li>{render_runoff_round(round, room_state)}</li>
+                    }
+                </ol>
             </div>
+        },
+        TallyBreakdown::Pairwise(matrix) => render_pairwise_matrix(matrix, room_state),
+        TallyBreakdown::StarRunoff(runoff) => render_star_runoff(runoff, room_state),
+    }
+}
+
+fn render_pairwise_matrix(matrix: &PairwiseMatrix, room_state: &RoomState) -> Html<AppMsg> {
+    let option_texts = matrix
+        .options
+        .iter()
+        .map(|id| room_state.get_option_html_displayable_text(id))
+        .collect::<Vec<_>>();
+    let condorcet_text = match &matrix.condorcet_winner {
+        Some(id) => format!(
+            "{} is the Condorcet winner: a majority preferred it head-to-head over every other option.",
+            room_state.get_option_html_displayable_text(id)
+        ),
+        None => "There is no Condorcet winner, so the winner was chosen by comparing the strongest chains of head-to-head wins.".to_string(),
+    };
+    let rows = matrix
+        .preferences
+        .iter()
+        .enumerate()
+        .map(|(i, row)| {
+            let cells = row
+                .iter()
+                .enumerate()
+                .map(|(j, count)| {
+                    if i == j {
+                        "-".to_string()
+                    } else {
+                        count.to_string()
+                    }
+                })
+                .collect::<Vec<_>>();
+            (option_texts[i].clone(), cells)
+        })
+        .collect::<Vec<_>>();
+    html! {
+        <div>
+            <h4>"Head-to-Head"</h4>
+            <p>{condorcet_text}</p>
+            <p>"Each cell shows how many voters preferred the row's option over the column's option."</p>
+            <table>
+                <thead>
+                    <tr>
+                        <th></th>
+                        for text in option_texts.iter() {
+                            <th>{text.clone()}</th>
+                        }
+                    </tr>
+                </thead>
+                <tbody>
+                    for row in rows.iter() {
+                        <tr>
+                            <th>{row.0.clone()}</th>
+                            for cell in row.1.iter() {
+                                <td>{cell.clone()}</td>
+                            }
+                        </tr>
+                    }
+                </tbody>
+            </table>
+        </div>
+    }
+}
+
+fn render_runoff_round(round: &RunoffRound, room_state: &RoomState) -> Html<AppMsg> {
+    let option_text = |id: &Uuid| room_state.get_option_html_displayable_text(id);
+    let counts = round
+        .counts
+        .iter()
+        .map(|(id, count)| format!("{}: {}", option_text(id), count))
+        .collect::<Vec<_>>();
+    let eliminated_text = match (&round.eliminated, &round.elimination_tie) {
+        (None, _) => "Nobody eliminated, counting finished".to_string(),
+        (Some(eliminated), None) => format!("Eliminated: {}", option_text(eliminated)),
+        (Some(eliminated), Some(tie)) => format!(
+            "Eliminated: {} ({} were tied for the fewest votes, {})",
+            option_text(eliminated),
+            tie.tied.iter().map(option_text).join(", "),
+            get_elimination_tie_break_text(tie.decided_by)
+        ),
+    };
+    let transfers = round
+        .transfers
+        .iter()
+        .map(|(to, count)| {
+            let destination = to.as_ref().map_or(EXHAUSTED_TEXT.to_string(), option_text);
+            format!("{} moved to {}", pluralize_votes(*count), destination)
+        })
+        .collect::<Vec<_>>();
+    html! {
+        <div>
+            <ul>
+                for count in counts.iter() {
+                    <li>{count.clone()}</li>
+                }
+            </ul>
+            <p>{eliminated_text}</p>
+            <ul>
+                for transfer in transfers.iter() {
+                    <li>{transfer.clone()}</li>
+                }
+            </ul>
+        </div>
+    }
+}
+
+fn get_elimination_tie_break_text(decided_by: EliminationTieBreak) -> String {
+    match decided_by {
+        EliminationTieBreak::EarlierRound => {
+            "so the one with the fewest votes in an earlier round went".to_string()
+        }
+        EliminationTieBreak::Policy(policy) => format!(
+            "so the tie-break policy ({}) picked which went",
+            policy.display_name().to_ascii_lowercase()
+        ),
+        EliminationTieBreak::OptionOrder => {
+            "and nothing separated them, so the last in option order went".to_string()
+        }
+    }
+}
+
+fn render_star_runoff(runoff: &StarRunoff, room_state: &RoomState) -> Html<AppMsg> {
+    let (first, second) = (
+        room_state.get_option_html_displayable_text(&runoff.finalists.0),
+        room_state.get_option_html_displayable_text(&runoff.finalists.1),
+    );
+    html! {
+        <div>
+            <h4>"Automatic Runoff"</h4>
+            <p>{format!("The two highest-scoring options, {} and {}, went to a runoff.", first, second)}</p>
+            <ul>
+                <li>{format!("{} scored higher on {}", first, pluralize_ballots(runoff.preferred_counts.0))}</li>
+                <li>{format!("{} scored higher on {}", second, pluralize_ballots(runoff.preferred_counts.1))}</li>
+                <li>{format!("Both scored equally on {}", pluralize_ballots(runoff.no_preference))}</li>
+            </ul>
+        </div>
+    }
+}
+
+fn pluralize_ballots(count: usize) -> String {
+    if count == 1 {
+        "1 ballot".to_string()
+    } else {
+        format!("{} ballots", count)
+    }
+}
+
+fn pluralize_votes(count: usize) -> String {
+    if count == 1 {
+        "1 vote".to_string()
+    } else {
+        format!("{} votes", count)
+    }
+}
+
+fn get_tie_break_text(
+    tie_break: &TieBreak,
+    tallies: &[FinalVoteTally],
+    room_state: &RoomState,
+) -> String {
+    let tied_text = tie_break
+        .tied
+        .iter()
+        .map(|id| room_state.get_option_html_displayable_text(id))
+        .join(", ");
+    let score = tallies
+        .iter()
+        .find(|t| tie_break.tied.contains(&t.id))
+        .map_or(0, |t| t.score);
+    let how = match tie_break.policy {
+        TieBreakPolicy::MostFirstPlace => "by counting first-place votes".to_string(),
+        TieBreakPolicy::HeadToHead => "by their head-to-head results".to_string(),
+        TieBreakPolicy::SeededRandom => format!(
+            "by a random draw with seed {}",
+            room_state.settings().tie_break_seed
+        ),
+        TieBreakPolicy::DeclareTie => {
+            return format!(
+                "{} tied with a score of {} and share a place.",
+                tied_text, score
+            )
         }
+    };
+
+    if tie_break.resolved {
+        format!(
+            "{} tied with a score of {}. The tie was broken {}.",
+            tied_text, score, how
+        )
+    } else {
+        format!(
+            "{} tied with a score of {}. The tie couldn't be fully broken {}, so some of them share a place.",
+            tied_text, score, how
+        )
+    }
+}
+
+fn get_voter_text(voter: &ParticipantId, viewer: ParticipantId, room_state: &RoomState) -> String {
+    let nickname = room_state.get_participant_html_nickname(voter);
+    if *voter == viewer {
+        format!("{} (you)", nickname)
+    } else {
+        nickname
+    }
+}
+
+fn render_tally_details(tally: &FinalVoteTally, room_state: &RoomState) -> Html<AppMsg> {
+    match room_state.get_option(&tally.id) {
+        Some(option) => render_option_details(option.details()),
+        None => render_option_details(&OptionDetails::default()),
     }
 }
 
 fn get_summary_text(tally: &FinalVoteTally) -> String {
-    format!(
-        "{} ({}) - {}",
-        tally.html_displayable_text,
-        get_ranks_text(&tally.ranks),
-        tally.score
-    )
+    if tally.ranks.is_empty() {
+        format!("{} - {}", tally.html_displayable_text, tally.score)
+    } else {
+        format!(
+            "{} ({}) - {}",
+            tally.html_displayable_text,
+            get_ranks_text(&tally.ranks),
+            tally.score
+        )
+    }
 }
 
 fn get_ranks_text(ranks: &[usize]) -> String {
@@ -1,18 +1,42 @@
-use super::{deserialize_form, AppPage, AppUpdateResponse};
-use crate::{app::AppMsg, pages::veto_page::VetoPage};
+use super::{deserialize_form, option_details::render_option_details, AppPage, AppUpdateResponse};
+use crate::{
+    app::AppMsg,
+    pages::veto_page::VetoPage,
+    room_state::{
+        ballot::PartialBallotPolicy,
+        import::{import_options, ImportedOption},
+        participant::{Participant, ParticipantId, MAX_NICKNAME_LENGTH},
+        tally::TallyMethodKind,
+        tie_break::TieBreakPolicy,
+        veto_completion::{default_ready_quorum_percent, VetoCompletion},
+        veto_rules::default_veto_threshold,
+        RoomSettings,
+    },
+};
 use axum::http::Uri;
 use axum_live_view::{html, js_command};
 use serde::{Deserialize, Serialize};
 
-#[derive(Default)]
 pub struct RoomChoicePage {
+    participant: ParticipantId,
     join_error_msg: Option<String>,
+    create_error_msg: Option<String>,
+    /// Describes what the options typed in so far were read as, or why they couldn't be
+    options_preview_msg: String,
+    options_preview: Vec<ImportedOption>,
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
 pub enum RoomChoiceMsg {
     JoinRoom,
     CreateRoom,
+    PreviewOptions,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct OptionsPreviewFormSubmit {
+    #[serde(default)]
+    options_text: String,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -22,12 +46,63 @@ pub struct JoinRoomFormSubmit {
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct CreateRoomFormSubmit {
+    nickname: String,
     options_text: String,
+    #[serde(default)]
+    tally_method: TallyMethodKind,
+    #[serde(default)]
+    tie_break: TieBreakPolicy,
+    #[serde(default)]
+    partial_ballots: PartialBallotPolicy,
+    #[serde(default)]
+    veto_completion: VetoCompletion,
+    /// The number fields are read as text, since a blank field doesn't deserialize as a number
+    #[serde(default)]
+    ready_quorum_percent: String,
+    /// Left blank for no limit
+    #[serde(default)]
+    veto_limit: String,
+    #[serde(default)]
+    veto_threshold: String,
+}
+
+impl CreateRoomFormSubmit {
+    fn settings(&self) -> Result<RoomSettings, String> {
+        let ready_quorum_percent = parse_number_field(
+            &self.ready_quorum_percent,
+            "The share of people who have to be done vetoing",
+            1,
+            100,
+        )?;
+        let veto_limit = parse_number_field(&self.veto_limit, "Vetoes per person", 0, u8::MAX)?;
+        let veto_threshold = parse_number_field(
+            &self.veto_threshold,
+            "Vetoes needed to strike an option",
+            1,
+            u8::MAX,
+        )?;
+        Ok(RoomSettings {
+            tally_method: self.tally_method,
+            tie_break: self.tie_break,
+            partial_ballots: self.partial_ballots,
+            veto_completion: self.veto_completion,
+            ready_quorum_percent: ready_quorum_percent.unwrap_or_else(default_ready_quorum_percent),
+            veto_limit,
+            veto_threshold: veto_threshold.unwrap_or_else(default_veto_threshold),
+            ..Default::default()
+        })
+    }
 }
 
 impl RoomChoicePage {
-    pub fn new(join_error_msg: Option<String>) -> Self {
-        Self { join_error_msg }
+    pub fn new(participant: ParticipantId, join_error_msg: Option<String>) -> Self {
+        Self {
+            participant,
+            join_error_msg,
+            create_error_msg: None,
+            options_preview_msg: String::new(),
+            options_preview: Vec::new(),
+        }
     }
 }
 
@@ -42,12 +117,10 @@ impl AppPage for RoomChoicePage {
         if let AppMsg::RoomChoiceMsg(msg) = msg {
             match msg {
                 RoomChoiceMsg::JoinRoom => {
-                    let code = deserialize_form::<JoinRoomFormSubmit>(data)?
-                        .room_code
-                        .to_ascii_lowercase();
+                    let code = deserialize_form::<JoinRoomFormSubmit>(data)?.room_code;
 
                     let state = server_shared_state.read().unwrap();
-                    match state.get_room_voting_page(&code) {
+                    match state.get_room_voting_page(&code, self.participant) {
                         Ok((page, broadcast_rx)) => {
                             broadcast_rx_tx.send(broadcast_rx)?;
                             return Ok((
@@ -59,12 +132,52 @@ impl AppPage for RoomChoicePage {
                         Err(msg) => self.join_error_msg = Some(msg),
                     }
                 }
+                RoomChoiceMsg::PreviewOptions => {
+                    let text = deserialize_form::<OptionsPreviewFormSubmit>(data)?.options_text;
+                    (self.options_preview_msg, self.options_preview) = if text.trim().is_empty() {
+                        (String::new(), Vec::new())
+                    } else {
+                        match import_options(&text) {
+                            Ok((format, options)) => (
+                                format!(
+                                    "Found {} options, read as {}:",
+                                    options.len(),
+                                    format.display_name()
+                                ),
+                                options,
+                            ),
+                            Err(e) => (format!("Error: {}", e), Vec::new()),
+                        }
+                    };
+                }
                 RoomChoiceMsg::CreateRoom => {
-                    let options_text = deserialize_form::<CreateRoomFormSubmit>(data)?.options_text;
+                    let form = deserialize_form::<CreateRoomFormSubmit>(data)?;
+                    let creator = match Participant::new(self.participant, &form.nickname) {
+                        Ok(creator) => creator,
+                        Err(e) => {
+                            self.create_error_msg = Some(e.to_string());
+                            return Ok((None, None).into());
+                        }
+                    };
+                    let settings = match form.settings() {
+                        Ok(settings) => settings,
+                        Err(e) => {
+                            self.create_error_msg = Some(e);
+                            return Ok((None, None).into());
+                        }
+                    };
+
+                    let options = match import_options(&form.options_text) {
+                        Ok((_, options)) => options,
+                        Err(e) => {
+                            self.create_error_msg = Some(e.to_string());
+                            return Ok((None, None).into());
+                        }
+                    };
 
                     let mut state = server_shared_state.write().unwrap();
                     if let Ok((room_code, room, broadcast_tx, broadcast_rx)) =
-                        state.create_room(options_text)
+                        state.create_room(options, settings, creator)
                     {
                         let cmd = js_command::history_push_state(room_uri(&room_code));
                         broadcast_rx_tx.send(broadcast_rx)?;
@@ -73,6 +186,7 @@ impl AppPage for RoomChoicePage {
                                 room_code,
                                 room.clone(),
                                 broadcast_tx.clone(),
+                                self.participant,
                             ))
                                 as Box<dyn AppPage + Send + Sync>),
                             Some(vec![cmd]),
@@ -106,17 +220,97 @@ impl AppPage for RoomChoicePage {
                 <p>{ammonia::clean_text(&self.join_error_msg.as_ref().map_or("".to_string(), |s| format!("Error: {}", s)))}</p> // Doing this instead of the more intuitive `if let Some ...` approach as that causes a strange bug that turns things into <p> els on the next page
 
                 <h1>"Create Room"</h1>
-                <p>"Create a new room by entering the options below, each on its own line."</p>
-                <form axm-submit={ AppMsg::RoomChoiceMsg(RoomChoiceMsg::CreateRoom) }>
-                    <textarea name="options_text" rows="10">
+                <p>"Create a new room by entering the options below, each on its own line. You can also paste or upload a CSV with title, description and link columns, a JSON list, or a Markdown list, where ticked checkboxes are left out."</p>
+                <form axm-change={ AppMsg::RoomChoiceMsg(RoomChoiceMsg::PreviewOptions) } axm-submit={ AppMsg::RoomChoiceMsg(RoomChoiceMsg::CreateRoom) }>
+                    <input
+                        type="text"
+                        name="nickname"
+                        maxlength={MAX_NICKNAME_LENGTH.to_string()}
+                        placeholder="Your nickname"
+                    />
+                    <textarea id="optionsTextInput" name="options_text" rows="10">
                     </textarea>
+                    <label for="optionsFileInput">"Or upload a list"</label>
+                    <input type="file" id="optionsFileInput" accept=".csv,.tsv,.json,.md,.txt"/>
+                    <p>{ammonia::clean_text(&self.options_preview_msg)}</p>
+                    <ol>
+                        for option in self.options_preview.iter() {
+                            <li>{ammonia::clean_text(&option.text)}{render_option_details(&option.details)}</li>
+                        }
+                    </ol>
+                    <label for="tallyMethodSelect">"Voting method"</label>
+                    <select id="tallyMethodSelect" name="tally_method">
+                        for method in TallyMethodKind::ALL.iter() {
+                            <option value={method.form_value()}>{method.display_name()}</option>
+                        }
+                    </select>
+                    <label for="tieBreakSelect">"Breaking ties"</label>
+                    <select id="tieBreakSelect" name="tie_break">
+                        for policy in TieBreakPolicy::ALL.iter() {
+                            <option value={policy.form_value()}>{policy.display_name()}</option>
+                        }
+                    </select>
+                    <label for="partialBallotSelect">"Partially ranked ballots"</label>
+                    <select id="partialBallotSelect" name="partial_ballots">
+                        for policy in PartialBallotPolicy::ALL.iter() {
+                            <option value={policy.form_value()}>{policy.display_name()}</option>
+                        }
+                    </select>
+                    <label for="vetoCompletionSelect">"Ending the veto phase"</label>
+                    <select id="vetoCompletionSelect" name="veto_completion">
+                        for completion in VetoCompletion::ALL.iter() {
+                            <option value={completion.form_value()}>{completion.display_name()}</option>
+                        }
+                    </select>
+                    <label for="readyQuorumInput">"Share of people who have to be done vetoing (%)"</label>
+                    <input
+                        type="number"
+                        id="readyQuorumInput"
+                        name="ready_quorum_percent"
+                        min="1"
+                        max="100"
+                        value={default_ready_quorum_percent().to_string()}
+                    />
+                    <label for="vetoLimitInput">"Vetoes per person (leave blank for no limit)"</label>
+                    <input
+                        type="number"
+                        id="vetoLimitInput"
+                        name="veto_limit"
+                        min="0"
+                        max="255"
+                    />
+                    <label for="vetoThresholdInput">"Vetoes needed to strike an option"</label>
+                    <input
+                        type="number"
+                        id="vetoThresholdInput"
+                        name="veto_threshold"
+                        min="1"
+                        max="255"
+                        value={default_veto_threshold().to_string()}
+                    />
                     <input type="submit" value="Create Room"/>
                 </form>
+                <p>{ammonia::clean_text(&self.create_error_msg.as_ref().map_or("".to_string(), |s| format!("Error: {}", s)))}</p>
             </div>
         }
     }
 }
 
+/// Reads a whole number typed into one of the form's fields, or `None` if it was left blank
+fn parse_number_field(value: &str, label: &str, min: u8, max: u8) -> Result<Option<u8>, String> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Ok(None);
+    }
+    match value.parse() {
+        Ok(number) if (min..=max).contains(&number) => Ok(Some(number)),
+        _ => Err(format!(
+            "{} must be a whole number from {} to {}",
+            label, min, max
+        )),
+    }
+}
+
 fn room_uri(room_code: &str) -> Uri {
     format!("/room/{}", room_code).parse().unwrap()
 }
@@ -0,0 +1,120 @@
+use std::sync::{Arc, RwLock};
+
+use axum_live_view::{html, Html};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app::AppMsg,
+    room_state::{
+        participant::{Participant, ParticipantId},
+        RoomState,
+    },
+};
+
+/// Moderation actions the host can take from the roster on any of the room's pages
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub enum HostMsg {
+    MakeHost(ParticipantId),
+    Kick(ParticipantId),
+    Lock,
+    Unlock,
+    CloseVoting,
+}
+
+/// Carries out a host's action on the room, which lets everyone in it know
+pub fn update_as_host(
+    msg: HostMsg,
+    room_state: &Arc<RwLock<RoomState>>,
+    participant: ParticipantId,
+) -> anyhow::Result<()> {
+    let mut room_state = room_state.write().unwrap();
+    match msg {
+        HostMsg::MakeHost(id) => room_state.transfer_host(participant, id),
+        HostMsg::Kick(id) => room_state.kick(participant, id),
+        HostMsg::Lock => room_state.set_locked(participant, true),
+        HostMsg::Unlock => room_state.set_locked(participant, false),
+        HostMsg::CloseVoting => room_state.close_voting(participant),
+    }
+}
+
+/// Lists everyone who has joined the room, whether they're here right now, and how far along they are.
+/// The host also gets their moderation controls here.
+pub fn render_roster(room_state: &RoomState, viewer: ParticipantId) -> Html<AppMsg> {
+    let is_host = room_state.is_host(&viewer);
+    let entries = room_state
+        .iter_participants()
+        .map(|participant| {
+            (
+                participant.id,
+                get_roster_entry_text(participant, viewer, room_state),
+            )
+        })
+        .collect::<Vec<_>>();
+    let lock_text = if room_state.is_locked() {
+        "The room is locked, so nobody new can join."
+    } else {
+        ""
+    };
+    html! {
+        <div>
+            <h4>"Who's Here"</h4>
+            <ul>
+                for (id, entry) in entries.iter() {
+                    <li>
+                        {entry.clone()}
+                        if is_host && *id != viewer {
+                            " "
+                            <button style="font-size:0.75rem;" axm-click={AppMsg::HostMsg(HostMsg::MakeHost(*id))}>"Make host"</button>
+                            " "
+                            <button style="font-size:0.75rem;" axm-click={AppMsg::HostMsg(HostMsg::Kick(*id))}>"Kick"</button>
+                        }
+                    </li>
+                }
+            </ul>
+            <p>{lock_text}</p>
+            if is_host {
+                if room_state.is_locked() {
+                    <button style="font-size:0.75rem;" axm-click={AppMsg::HostMsg(HostMsg::Unlock)}>"Unlock room"</button>
+                } else {
+                    <button style="font-size:0.75rem;" axm-click={AppMsg::HostMsg(HostMsg::Lock)}>"Lock room"</button>
+                }
+                if room_state.is_voting_open() {
+                    " "
+                    <button style="font-size:0.75rem;" axm-click={AppMsg::HostMsg(HostMsg::CloseVoting)}>"Close voting"</button>
+                }
+            }
+        </div>
+    }
+}
+
+fn get_roster_entry_text(
+    participant: &Participant,
+    viewer: ParticipantId,
+    room_state: &RoomState,
+) -> String {
+    let you_text = if participant.id == viewer {
+        " (you)"
+    } else {
+        ""
+    };
+    let host_text = if room_state.is_host(&participant.id) {
+        " (host)"
+    } else {
+        ""
+    };
+    let away_text = if room_state.is_online(&participant.id) {
+        ""
+    } else {
+        ", away"
+    };
+    format!(
+        "{}{}{}: {}{}",
+        participant.get_html_nickname(),
+        you_text,
+        host_text,
+        room_state
+            .get_participant_status(&participant.id)
+            .display_name(),
+        away_text
+    )
+}
@@ -0,0 +1,133 @@
+use crate::{
+    app::AppMsg,
+    room_state::{
+        ballot::{Ballot, MAX_SCORE},
+        participant::ParticipantId,
+        RoomState,
+    },
+    BroadcastSender, ServerwideSharedState,
+};
+use axum_live_view::html;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+
+use super::{
+    deserialize_form, get_results_page_response, roster::render_roster, AppPage, AppUpdateResponse,
+};
+
+pub struct ScorePage {
+    pub room_code: String,
+    pub room_state: Arc<RwLock<RoomState>>,
+    pub broadcast_tx: BroadcastSender,
+    participant: ParticipantId,
+}
+
+impl ScorePage {
+    pub fn new(
+        room_code: String,
+        room_state: Arc<RwLock<RoomState>>,
+        broadcast_tx: BroadcastSender,
+        participant: ParticipantId,
+    ) -> Self {
+        Self {
+            room_code,
+            room_state,
+            broadcast_tx,
+            participant,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub enum ScoreMsg {
+    SubmitScores,
+    JustViewResults,
+}
+
+impl AppPage for ScorePage {
+    fn update(
+        &mut self,
+        msg: AppMsg,
+        data: Option<axum_live_view::event_data::EventData>,
+        _server_shared_state: &mut ServerwideSharedState,
+        _broadcast_rx_tx: &mut crate::BroadcastReceiverSender,
+    ) -> anyhow::Result<AppUpdateResponse> {
+        if let AppMsg::ScoreMsg(msg) = msg {
+            match msg {
+                ScoreMsg::SubmitScores => {
+                    // Each option's score is submitted keyed by option id
+                    let submitted = deserialize_form::<HashMap<String, String>>(data)?;
+                    {
+                        let mut room_state = self.room_state.write().unwrap();
+                        let scores = room_state
+                            .iter_options()
+                            .filter(|o| !o.vetoed)
+                            .map(|o| {
+                                let score = submitted
+                                    .get(&o.id.as_simple().to_string())
+                                    .and_then(|s| s.parse::<u8>().ok())
+                                    .unwrap_or(0)
+                                    .min(MAX_SCORE);
+                                (o.id, score)
+                            })
+                            .collect();
+                        room_state.contribute_ballot(self.participant, Ballot::Score(scores))?;
+                    }
+                    return get_results_page_response(
+                        &self.room_code,
+                        &self.room_state,
+                        self.participant,
+                    );
+                }
+                ScoreMsg::JustViewResults => {
+                    return get_results_page_response(
+                        &self.room_code,
+                        &self.room_state,
+                        self.participant,
+                    );
+                }
+            }
+        }
+
+        Ok((None, None).into())
+    }
+
+    fn render(&self) -> axum_live_view::Html<crate::app::AppMsg> {
+        let room_state = self.room_state.read().unwrap();
+        let previous_ballot = room_state.get_ballot(&self.participant);
+        html! {
+            <div>
+                <h1>"Now, score!"</h1>
+                <h3>{format!("Room: {}", self.room_code)}</h3>
+                <p>{format!("Score each option from 0 (not at all) to {} (absolutely). The two highest-scoring options go to an automatic runoff.", MAX_SCORE)}</p>
+                <button style="font-size:0.75rem;" axm-click={AppMsg::ScoreMsg(ScoreMsg::JustViewResults)}>"View Results w/o Voting"</button>
+                <form axm-submit={ AppMsg::ScoreMsg(ScoreMsg::SubmitScores) }>
+                    for option in room_state.iter_options().filter(|o| !o.vetoed) {
+                        <p>
+                            <label for={option.id.as_simple().to_string()}>{option.get_html_text()}</label>
+                            <select id={option.id.as_simple().to_string()} name={option.id.as_simple().to_string()}>
+                                for score in 0..=MAX_SCORE {
+                                    if previous_ballot.is_some_and(|ballot| ballot.score_of(&option.id) == score) {
+                                        <option value={score.to_string()} selected>{score.to_string()}</option>
+                                    } else {
+                                        <option value={score.to_string()}>{score.to_string()}</option>
+                                    }
+                                }
+                            </select>
+                        </p>
+                    }
+
+                    <input type="submit" value="Submit Scores"/>
+                </form>
+                {render_roster(&room_state, self.participant)}
+            </div>
+        }
+    }
+
+    fn room_state(&self) -> Option<&Arc<RwLock<RoomState>>> {
+        Some(&self.room_state)
+    }
+}
@@ -1,5 +1,18 @@
-use super::{deserialize_form, ranking_page::RankingPage, AppPage, AppUpdateResponse};
-use crate::{app::AppMsg, room_state::RoomState, BroadcastMsg, BroadcastSender};
+use super::{
+    deserialize_form, get_voting_stage_page, option_details::render_option_details,
+    roster::render_roster, AppPage, AppUpdateResponse,
+};
+use crate::{
+    app::AppMsg,
+    room_state::{
+        option_details::{OptionDetails, MAX_DESCRIPTION_LENGTH},
+        participant::ParticipantId,
+        veto_completion::VetoCompletion,
+        veto_rules::{describe_veto_rules, MAX_VETO_REASON_LENGTH},
+        Option as RoomOption, RoomState,
+    },
+    BroadcastSender,
+};
 use axum_live_view::html;
 use serde::{Deserialize, Serialize};
 use std::sync::{Arc, RwLock};
@@ -10,26 +23,35 @@ pub struct VetoPage {
     pub room_code: String,
     pub room_state: Arc<RwLock<RoomState>>,
     broadcast_tx: BroadcastSender,
+    participant: ParticipantId,
+    option_error_msg: Option<String>,
+    /// The option whose rename form is open, if any
+    renaming_option: Option<Uuid>,
 }
 impl VetoPage {
     pub fn new(
         room_code: String,
         room_state: Arc<RwLock<RoomState>>,
         broadcast_tx: BroadcastSender,
+        participant: ParticipantId,
     ) -> Self {
         Self {
             room_code,
             room_state,
             broadcast_tx,
+            participant,
+            option_error_msg: None,
+            renaming_option: None,
         }
     }
 
-    fn get_ranking_page(&mut self) -> Option<Box<dyn AppPage + Send + Sync>> {
-        Some(Box::new(RankingPage::new(
+    fn get_voting_page(&mut self) -> Option<Box<dyn AppPage + Send + Sync>> {
+        Some(get_voting_stage_page(
             self.room_code.clone(),
             self.room_state.clone(),
             self.broadcast_tx.clone(),
-        )))
+            self.participant,
+        ))
     }
 }
 
@@ -41,11 +63,36 @@ pub enum VetoMsg {
     FinishVetoing,
     OtherUserFinishedVetoing,
     AddOption,
+    MarkReady,
+    MarkNotReady,
+    RemoveOption(String),
+    StartRenamingOption(String),
+    RenameOption(String),
+    StopRenamingOption,
+    /// Takes back one participant's veto of an option, identified as (option id, vetoer id)
+    UndoVeto(String, String),
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct VetoFormSubmit {
+    #[serde(default)]
+    reason: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RenameOptionFormSubmit {
+    text: String,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct AddOptionFormSubmit {
     option: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    url: String,
+    #[serde(default)]
+    image_url: String,
 }
 
 impl AppPage for VetoPage {
@@ -60,39 +107,134 @@ impl AppPage for VetoPage {
             match msg {
                 VetoMsg::VetoOption(id_to_veto) => {
                     if let Ok(uuid) = Uuid::parse_str(&id_to_veto) {
-                        self.room_state.write().unwrap().veto(uuid);
-                        self.broadcast_tx.send(BroadcastMsg::UpdatedVetos)?;
+                        let reason = deserialize_form::<VetoFormSubmit>(data)?.reason;
+                        self.room_state
+                            .write()
+                            .unwrap()
+                            .veto(self.participant, uuid, &reason)?;
                     } else {
                         warn!("Received invalid uuid to veto: {}", id_to_veto);
                     }
                 }
                 VetoMsg::VetosUpdated => (),
                 VetoMsg::ResetAllVetos => {
-                    self.room_state.write().unwrap().reset_all_vetos();
-                    self.broadcast_tx.send(BroadcastMsg::UpdatedVetos)?;
+                    self.room_state
+                        .write()
+                        .unwrap()
+                        .reset_all_vetos(self.participant)?;
+                }
+                VetoMsg::UndoVeto(option_id, vetoer_id) => {
+                    match (
+                        Uuid::parse_str(&option_id),
+                        vetoer_id.parse::<ParticipantId>(),
+                    ) {
+                        (Ok(uuid), Ok(vetoer)) => {
+                            self.room_state.write().unwrap().undo_veto(
+                                self.participant,
+                                uuid,
+                                vetoer,
+                            )?;
+                        }
+                        _ => warn!(
+                            "Received invalid ids to undo veto: {}, {}",
+                            option_id, vetoer_id
+                        ),
+                    }
+                }
+                VetoMsg::StartRenamingOption(id_to_rename) => {
+                    self.renaming_option = Uuid::parse_str(&id_to_rename).ok();
+                }
+                VetoMsg::StopRenamingOption => self.renaming_option = None,
+                VetoMsg::RenameOption(id_to_rename) => {
+                    if let Ok(uuid) = Uuid::parse_str(&id_to_rename) {
+                        let text = deserialize_form::<RenameOptionFormSubmit>(data)?.text;
+                        let renamed = self.room_state.write().unwrap().rename_option(
+                            self.participant,
+                            uuid,
+                            text,
+                        );
+                        match renamed {
+                            Ok(()) => {
+                                self.option_error_msg = None;
+                                self.renaming_option = None;
+                            }
+                            Err(e) => self.option_error_msg = Some(e.to_string()),
+                        }
+                    } else {
+                        warn!("Received invalid uuid to rename: {}", id_to_rename);
+                    }
+                }
+                VetoMsg::RemoveOption(id_to_remove) => {
+                    if let Ok(uuid) = Uuid::parse_str(&id_to_remove) {
+                        self.room_state
+                            .write()
+                            .unwrap()
+                            .remove_option(self.participant, uuid)?;
+                    } else {
+                        warn!("Received invalid uuid to remove: {}", id_to_remove);
+                    }
                 }
                 VetoMsg::AddOption => {
-                    let mut option = deserialize_form::<AddOptionFormSubmit>(data)?.option;
-                    option = option.trim().to_string();
+                    let form = deserialize_form::<AddOptionFormSubmit>(data)?;
+                    let option = form.option.trim().to_string();
                     if !option.is_empty() {
-                        self.room_state.write().unwrap().add_option(option);
-                        self.broadcast_tx.send(BroadcastMsg::UpdatedVetos)?;
+                        let details =
+                            match OptionDetails::new(&form.description, &form.url, &form.image_url)
+                            {
+                                Ok(details) => details,
+                                Err(e) => {
+                                    self.option_error_msg = Some(e.to_string());
+                                    return Ok((None, None).into());
+                                }
+                            };
+                        self.option_error_msg = None;
+                        self.room_state.write().unwrap().add_option(
+                            self.participant,
+                            option,
+                            details,
+                        )?;
                         return Ok((
                             None,
-                            Some(vec![axum_live_view::js_command::clear_value(
-                                "#newOptionInput",
-                            )]),
+                            Some(
+                                [
+                                    "#newOptionInput",
+                                    "#newOptionDescriptionInput",
+                                    "#newOptionUrlInput",
+                                    "#newOptionImageUrlInput",
+                                ]
+                                .into_iter()
+                                .map(axum_live_view::js_command::clear_value)
+                                .collect(),
+                            ),
                         )
                             .into());
                     }
                 }
                 VetoMsg::FinishVetoing => {
-                    self.room_state.write().unwrap().finish_vetoing();
-                    self.broadcast_tx.send(BroadcastMsg::FinishedVetoing)?;
-                    return Ok((self.get_ranking_page(), None).into());
+                    self.room_state
+                        .write()
+                        .unwrap()
+                        .finish_vetoing(self.participant)?;
+                    return Ok((self.get_voting_page(), None).into());
+                }
+                VetoMsg::MarkReady => {
+                    let finished = self
+                        .room_state
+                        .write()
+                        .unwrap()
+                        .mark_ready(self.participant, true)?;
+                    if finished {
+                        return Ok((self.get_voting_page(), None).into());
+                    }
+                }
+                VetoMsg::MarkNotReady => {
+                    self.room_state
+                        .write()
+                        .unwrap()
+                        .mark_ready(self.participant, false)?;
                 }
                 VetoMsg::OtherUserFinishedVetoing => {
-                    return Ok((self.get_ranking_page(), None).into());
+                    return Ok((self.get_voting_page(), None).into());
                 }
             }
         }
@@ -105,11 +247,27 @@ impl AppPage for VetoPage {
         const BUTTON_TEXT: &str = "X";
         const BUTTON_SPACE: &str = "  ";
         let room_state = self.room_state.read().unwrap();
+        let is_ready_check = room_state.settings().veto_completion == VetoCompletion::ReadyCheck;
+        let is_ready = room_state.is_ready(&self.participant);
+        let is_host = room_state.is_host(&self.participant);
+        let remaining_vetoes = room_state.get_remaining_vetoes(&self.participant);
+        let out_of_vetoes = remaining_vetoes == Some(0);
+        let remaining_vetoes_text = remaining_vetoes.map_or("".to_string(), |remaining| {
+            format!("You have {} left.", pluralize_vetoes(remaining))
+        });
+        let ready_text = format!(
+            "{} of the {} people needed are done vetoing.",
+            room_state.ready_count(),
+            room_state.required_ready_count()
+        );
         html! {
             <div>
                 <h1>"It's veto time, baby!"</h1>
                 <h3>{format!("Room: {}", self.room_code)}</h3>
-                <p>"Any voter can veto any number of options. When the veto phase is finished, all voters proceed to the ranking phase together and can no longer veto. Voters can also add new options here, because why not."</p>
+                <p>"When the veto phase is finished, all voters proceed to the voting phase together and can no longer veto. Voters can also add new options here, because why not."</p>
+                <p>{describe_veto_rules(room_state.settings().veto_limit, room_state.settings().veto_threshold)}</p>
+                <p>{room_state.settings().veto_completion.description()}</p>
+                <p>{remaining_vetoes_text}</p>
                 <h4>"Options"</h4>
                 <div>
                     <ol>
@@ -117,10 +275,39 @@ impl AppPage for VetoPage {
                             <li>
                                 if option.vetoed {
                                     // Re-enabling by resetting vetoes doesn't allow re-vetoing unless I include the axm-click here, even though it's not necessary (since the button's disabled)
-                                    <s>{option.get_html_text()}</s>{BUTTON_SPACE}<button style={BUTTON_STYLE} disabled axm-click={ AppMsg::VetoMsg(VetoMsg::VetoOption(option.id.as_simple().to_string())) }>{BUTTON_TEXT}</button>
+                                    <s>{option.get_html_text()}</s>{get_vetoes_text(option, &room_state)}{BUTTON_SPACE}<button style={BUTTON_STYLE} disabled axm-click={ AppMsg::VetoMsg(VetoMsg::VetoOption(option.id.as_simple().to_string())) }>{BUTTON_TEXT}</button>
                                 } else {
-                                    {option.get_html_text()}{BUTTON_SPACE}<button style={BUTTON_STYLE} axm-click={ AppMsg::VetoMsg(VetoMsg::VetoOption(option.id.as_simple().to_string())) }>{BUTTON_TEXT}</button>
+                                    if out_of_vetoes || room_state.has_vetoed(&self.participant, &option.id) {
+                                        {option.get_html_text()}{get_vetoes_text(option, &room_state)}{BUTTON_SPACE}<button style={BUTTON_STYLE} disabled axm-click={ AppMsg::VetoMsg(VetoMsg::VetoOption(option.id.as_simple().to_string())) }>{BUTTON_TEXT}</button>
+                                    } else {
+                                        {option.get_html_text()}{get_vetoes_text(option, &room_state)}{BUTTON_SPACE}
+                                        <form style="display:inline;" axm-submit={ AppMsg::VetoMsg(VetoMsg::VetoOption(option.id.as_simple().to_string())) }>
+                                            <input
+                                                type="text"
+                                                name="reason"
+                                                style="display:inline;width:auto;font-size:0.75rem;"
+                                                maxlength={MAX_VETO_REASON_LENGTH.to_string()}
+                                                placeholder="Why? (optional)"
+                                            />
+                                            <input type="submit" style={BUTTON_STYLE} value={BUTTON_TEXT}/>
+                                        </form>
+                                    }
+                                }
+                                for veto in option.vetoes.iter().filter(|veto| is_host || veto.by == self.participant) {
+                                    {BUTTON_SPACE}<button style="font-size:0.75rem;" axm-click={ AppMsg::VetoMsg(VetoMsg::UndoVeto(option.id.as_simple().to_string(), veto.by.to_string())) }>{get_undo_veto_text(&veto.by, &room_state, self.participant)}</button>
                                 }
+                                if room_state.can_edit_option(&self.participant, &option.id) {
+                                    {BUTTON_SPACE}<button style="font-size:0.75rem;" axm-click={ AppMsg::VetoMsg(VetoMsg::StartRenamingOption(option.id.as_simple().to_string())) }>"Rename"</button>
+                                    {BUTTON_SPACE}<button style="font-size:0.75rem;" axm-click={ AppMsg::VetoMsg(VetoMsg::RemoveOption(option.id.as_simple().to_string())) }>"Remove"</button>
+                                }
+                                if self.renaming_option == Some(option.id) {
+                                    <form axm-submit={ AppMsg::VetoMsg(VetoMsg::RenameOption(option.id.as_simple().to_string())) }>
+                                        <input type="text" name="text" value={option.get_html_text()}/>
+                                        <input type="submit" value="Save"/>
+                                        {BUTTON_SPACE}<button type="button" style="font-size:0.75rem;" axm-click={ AppMsg::VetoMsg(VetoMsg::StopRenamingOption) }>"Cancel"</button>
+                                    </form>
+                                }
+                                {render_option_details(option.details())}
                             </li>
                         }
                     </ol>
@@ -132,16 +319,98 @@ impl AppPage for VetoPage {
                             name="option"
                             placeholder="New option"
                         />
+                        <input
+                            type="text"
+                            id="newOptionDescriptionInput"
+                            name="description"
+                            maxlength={MAX_DESCRIPTION_LENGTH.to_string()}
+                            placeholder="Description (optional)"
+                        />
+                        <input
+                            type="url"
+                            id="newOptionUrlInput"
+                            name="url"
+                            placeholder="Link, like a trailer or store page (optional)"
+                        />
+                        <input
+                            type="url"
+                            id="newOptionImageUrlInput"
+                            name="image_url"
+                            placeholder="Image link (optional)"
+                        />
 
                         <input type="submit" value="Add Option"/>
                     </form>
+                    <p>{ammonia::clean_text(&self.option_error_msg.as_ref().map_or("".to_string(), |s| format!("Error: {}", s)))}</p>
 
                     // This button seemingly has to be beneath the options, otherwise, the options don't get rendered...
-                    <button style="font-size:0.75rem;" axm-click={AppMsg::VetoMsg(VetoMsg::ResetAllVetos)}>"Reset all vetos"</button>
-                    " "
-                    <button axm-click={AppMsg::VetoMsg(VetoMsg::FinishVetoing)}>"Finish Vetoing"</button>
+                    if is_host {
+                        <button style="font-size:0.75rem;" axm-click={AppMsg::VetoMsg(VetoMsg::ResetAllVetos)}>"Reset all vetos"</button>
+                        " "
+                    }
+                    if is_ready_check {
+                        if is_ready {
+                            <button axm-click={AppMsg::VetoMsg(VetoMsg::MarkNotReady)}>"I'm not done after all"</button>
+                        } else {
+                            <button axm-click={AppMsg::VetoMsg(VetoMsg::MarkReady)}>"I'm done vetoing"</button>
+                        }
+                        if is_host {
+                            " "
+                            <button axm-click={AppMsg::VetoMsg(VetoMsg::FinishVetoing)}>"Move everyone on now"</button>
+                        }
+                        <p>{ready_text}</p>
+                    } else {
+                        // Anyone being ready moves everyone on in this mode
+                        <button axm-click={AppMsg::VetoMsg(VetoMsg::MarkReady)}>"Finish Vetoing"</button>
+                    }
                 </div>
+                {render_roster(&room_state, self.participant)}
             </div>
         }
     }
+
+    fn room_state(&self) -> Option<&Arc<RwLock<RoomState>>> {
+        Some(&self.room_state)
+    }
+}
+
+fn get_vetoes_text(option: &RoomOption, room_state: &RoomState) -> String {
+    if option.vetoes.is_empty() {
+        return "".to_string();
+    }
+
+    let vetoers = room_state.get_html_vetoes_text(option);
+    if option.vetoed {
+        format!(" (vetoed by {})", vetoers)
+    } else {
+        format!(
+            " ({} of {} needed: {})",
+            pluralize_vetoes(option.vetoes.len()),
+            room_state.settings().veto_threshold,
+            vetoers
+        )
+    }
+}
+
+fn get_undo_veto_text(
+    vetoer: &ParticipantId,
+    room_state: &RoomState,
+    viewer: ParticipantId,
+) -> String {
+    if *vetoer == viewer {
+        "Undo my veto".to_string()
+    } else {
+        format!(
+            "Undo {}'s veto",
+            room_state.get_participant_html_nickname(vetoer)
+        )
+    }
+}
+
+fn pluralize_vetoes(count: usize) -> String {
+    if count == 1 {
+        "1 veto".to_string()
+    } else {
+        format!("{} vetoes", count)
+    }
 }
@@ -0,0 +1,156 @@
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::{get_option_html_displayable_text, get_option_text, Options};
+
+pub const MAX_SCORE: u8 = 5;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BallotKind {
+    Ranked,
+    Approval,
+    Score,
+}
+
+/// How ranked ballots that leave some options unranked are counted
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PartialBallotPolicy {
+    /// Unranked options share last place below every ranked option
+    #[default]
+    UnrankedTieForLast,
+    /// A ballot says nothing about the options it leaves unranked
+    IgnoreUnranked,
+    /// A ballot's rankings are stretched to give out as many points as a complete ballot
+    Normalize,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum Ballot {
+    /// Options in order of preference, most preferred first
+    Ranked(Vec<Uuid>),
+    /// The options the voter would be happy to do
+    Approval(Vec<Uuid>),
+    /// A score from 0 to `MAX_SCORE` for each option
+    Score(Vec<(Uuid, u8)>),
+}
+
+impl PartialBallotPolicy {
+    pub const ALL: [PartialBallotPolicy; 3] = [
+        PartialBallotPolicy::UnrankedTieForLast,
+        PartialBallotPolicy::IgnoreUnranked,
+        PartialBallotPolicy::Normalize,
+    ];
+
+    pub fn form_value(&self) -> &'static str {
+        match self {
+            PartialBallotPolicy::UnrankedTieForLast => "UnrankedTieForLast",
+            PartialBallotPolicy::IgnoreUnranked => "IgnoreUnranked",
+            PartialBallotPolicy::Normalize => "Normalize",
+        }
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            PartialBallotPolicy::UnrankedTieForLast => "Unranked options tie for last",
+            PartialBallotPolicy::IgnoreUnranked => "Ignore unranked options",
+            PartialBallotPolicy::Normalize => "Normalize partial ballots",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            PartialBallotPolicy::UnrankedTieForLast => "Options left off a ballot tie for last place on it: they get no points and lose head-to-head to every option the ballot ranks.",
+            PartialBallotPolicy::IgnoreUnranked => "Options left off a ballot are ignored by it: a ballot ranking three options gives out 3, 2 and 1 points, and says nothing head-to-head about the options it leaves off.",
+            PartialBallotPolicy::Normalize => "Ballots that leave options off have their points stretched, rounded to the nearest point, so every ballot's first choice gets as many points as on a complete ballot. Unranked options lose head-to-head to ranked ones.",
+        }
+    }
+}
+
+impl Ballot {
+    pub fn kind(&self) -> BallotKind {
+        match self {
+            Ballot::Ranked(_) => BallotKind::Ranked,
+            Ballot::Approval(_) => BallotKind::Approval,
+            Ballot::Score(_) => BallotKind::Score,
+        }
+    }
+
+    /// Whether the ballot puts `id` in its top spot. Approvals count as a top spot, as does every
+    /// option given a ballot's highest (nonzero) score.
+    pub fn is_top_choice(&self, id: &Uuid) -> bool {
+        match self {
+            Ballot::Ranked(ids) => ids.first() == Some(id),
+            Ballot::Approval(ids) => ids.contains(id),
+            Ballot::Score(scores) => {
+                let highest = scores.iter().map(|(_, score)| *score).max().unwrap_or(0);
+                highest > 0 && self.score_of(id) == highest
+            }
+        }
+    }
+
+    /// Whether the ballot strictly prefers `a` over `b`
+    pub fn prefers(&self, a: &Uuid, b: &Uuid, policy: PartialBallotPolicy) -> bool {
+        match self {
+            Ballot::Ranked(ids) => {
+                match (
+                    ids.iter().position(|id| id == a),
+                    ids.iter().position(|id| id == b),
+                ) {
+                    (Some(position_a), Some(position_b)) => position_a < position_b,
+                    (Some(_), None) => policy != PartialBallotPolicy::IgnoreUnranked,
+                    _ => false,
+                }
+            }
+            Ballot::Approval(ids) => ids.contains(a) && !ids.contains(b),
+            Ballot::Score(_) => self.score_of(a) > self.score_of(b),
+        }
+    }
+
+    /// Every option the ballot mentions, in the order it mentions them
+    pub fn option_ids(&self) -> Vec<Uuid> {
+        match self {
+            Ballot::Ranked(ids) | Ballot::Approval(ids) => ids.clone(),
+            Ballot::Score(scores) => scores.iter().map(|(id, _)| *id).collect(),
+        }
+    }
+
+    /// The score the ballot gives `id`, or 0 if it isn't a score ballot
+    pub fn score_of(&self, id: &Uuid) -> u8 {
+        match self {
+            Ballot::Score(scores) => scores
+                .iter()
+                .find(|(scored, _)| scored == id)
+                .map_or(0, |(_, score)| *score),
+            _ => 0,
+        }
+    }
+
+    pub fn html_displayable_lines(&self, options: &Options) -> Vec<String> {
+        self.displayable_lines(options, get_option_html_displayable_text)
+    }
+
+    /// Like `html_displayable_lines`, but with the options' text as it was entered, for exports
+    pub fn text_lines(&self, options: &Options) -> Vec<String> {
+        self.displayable_lines(options, get_option_text)
+    }
+
+    fn displayable_lines(
+        &self,
+        options: &Options,
+        option_text: fn(&Options, &Uuid) -> String,
+    ) -> Vec<String> {
+        match self {
+            Ballot::Ranked(ids) | Ballot::Approval(ids) => {
+                ids.iter().map(|id| option_text(options, id)).collect()
+            }
+            Ballot::Score(scores) => scores
+                .iter()
+                .sorted_by_key(|(_, score)| std::cmp::Reverse(*score))
+                .map(|(id, score)| {
+                    format!("{} ({}/{})", option_text(options, id), score, MAX_SCORE)
+                })
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,475 @@
+use anyhow::{anyhow, bail};
+use itertools::Itertools;
+use serde::Serialize;
+use serde_json::Value;
+
+use super::option_details::{is_web_link, OptionDetails};
+
+const TITLE_COLUMNS: [&str; 4] = ["title", "name", "option", "text"];
+const DESCRIPTION_COLUMNS: [&str; 3] = ["description", "notes", "summary"];
+const URL_COLUMNS: [&str; 2] = ["link", "url"];
+const IMAGE_URL_COLUMNS: [&str; 4] = ["image", "image_url", "image link", "poster"];
+
+/// The ways a list of options can be written out when creating a room
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum ImportFormat {
+    /// Each non-blank line is an option
+    Lines,
+    /// Comma or tab separated, with a header row of at least two columns, one of them a title
+    Csv,
+    /// An array of strings, or of objects with a title and optionally a description and links
+    Json,
+    /// A bullet, numbered or checkbox list. Other lines, like headings, are skipped, as are ticked
+    /// checkboxes.
+    Markdown,
+}
+
+/// An option read from a room's starting list, before it's added to the room
+#[derive(Serialize)]
+pub struct ImportedOption {
+    pub text: String,
+    #[serde(flatten)]
+    pub details: OptionDetails,
+}
+
+impl ImportFormat {
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            ImportFormat::Lines => "one option per line",
+            ImportFormat::Csv => "CSV",
+            ImportFormat::Json => "JSON",
+            ImportFormat::Markdown => "Markdown list",
+        }
+    }
+
+    /// Works out which format the text is in. Anything that doesn't look like one of the structured
+    /// formats is read as one option per line.
+    pub fn detect(text: &str) -> Self {
+        let trimmed = text.trim_start();
+        let lines: Vec<&str> = text
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .collect();
+        let list_items = lines
+            .iter()
+            .filter(|line| strip_list_marker(line).is_some())
+            .count();
+        if trimmed.starts_with('[') && serde_json::from_str::<Value>(text).is_ok() {
+            ImportFormat::Json
+        } else if trimmed.lines().next().is_some_and(is_csv_header) {
+            ImportFormat::Csv
+        } else if list_items * 2 > lines.len() {
+            // Reading it as a list drops every other line, so most of them had better be list items
+            ImportFormat::Markdown
+        } else {
+            ImportFormat::Lines
+        }
+    }
+}
+
+impl ImportedOption {
+    pub fn new(text: String, details: OptionDetails) -> Self {
+        Self { text, details }
+    }
+}
+
+/// Reads the options out of pasted or uploaded text in whichever format it's in. Blank and repeated
+/// options are dropped.
+pub fn import_options(text: &str) -> anyhow::Result<(ImportFormat, Vec<ImportedOption>)> {
+    let format = ImportFormat::detect(text);
+    let options = match format {
+        ImportFormat::Lines => import_lines(text),
+        ImportFormat::Csv => import_csv(text)?,
+        ImportFormat::Json => import_json(text)?,
+        ImportFormat::Markdown => import_markdown(text)?,
+    };
+
+    Ok((
+        format,
+        options
+            .into_iter()
+            .filter(|o| !o.text.is_empty())
+            .unique_by(|o| o.text.clone())
+            .collect(),
+    ))
+}
+
+fn import_lines(text: &str) -> Vec<ImportedOption> {
+    text.lines()
+        .map(|line| ImportedOption::new(line.trim().to_string(), OptionDetails::default()))
+        .collect()
+}
+
+fn import_csv(text: &str) -> anyhow::Result<Vec<ImportedOption>> {
+    let text = text.trim_start();
+    let delimiter = csv_delimiter(text.lines().next().unwrap_or(""));
+    let mut records = parse_csv_records(text, delimiter).into_iter();
+    let header = records
+        .next()
+        .ok_or_else(|| anyhow!("The CSV is empty"))?
+        .iter()
+        .map(|column| column.trim().to_ascii_lowercase())
+        .collect::<Vec<_>>();
+    let title = find_csv_column(&header, &TITLE_COLUMNS)
+        .ok_or_else(|| anyhow!("The CSV needs a title column"))?;
+    let description = find_csv_column(&header, &DESCRIPTION_COLUMNS);
+    let url = find_csv_column(&header, &URL_COLUMNS);
+    let image_url = find_csv_column(&header, &IMAGE_URL_COLUMNS);
+
+    records
+        .filter(|record| record.iter().any(|field| !field.trim().is_empty()))
+        .map(|record| {
+            let field = |column: Option<usize>| {
+                column
+                    .and_then(|column| record.get(column))
+                    .map_or("", |field| field.as_str())
+            };
+            imported_option(
+                field(Some(title)),
+                field(description),
+                &normalize_link(field(url)),
+                &normalize_link(field(image_url)),
+            )
+        })
+        .collect()
+}
+
+fn import_json(text: &str) -> anyhow::Result<Vec<ImportedOption>> {
+    let items = match serde_json::from_str::<Value>(text)? {
+        Value::Array(items) => items,
+        _ => bail!("The JSON has to be a list of options"),
+    };
+
+    items
+        .iter()
+        .enumerate()
+        .map(|(index, item)| match item {
+            Value::String(text) => imported_option(text, "", "", ""),
+            Value::Object(fields) => {
+                let field = |names: &[&str]| {
+                    names
+                        .iter()
+                        .find_map(|name| fields.get(*name).and_then(Value::as_str))
+                        .unwrap_or("")
+                };
+                let title = field(&TITLE_COLUMNS);
+                if title.trim().is_empty() {
+                    bail!("Item {} in the JSON list needs a title", index + 1);
+                }
+                imported_option(
+                    title,
+                    field(&DESCRIPTION_COLUMNS),
+                    &normalize_link(field(&URL_COLUMNS)),
+                    &normalize_link(field(&IMAGE_URL_COLUMNS)),
+                )
+            }
+            _ => bail!(
+                "Item {} in the JSON list has to be text or an object",
+                index + 1
+            ),
+        })
+        .collect()
+}
+
+fn import_markdown(text: &str) -> anyhow::Result<Vec<ImportedOption>> {
+    text.lines()
+        .filter_map(strip_list_marker)
+        .filter_map(|item| match strip_checkbox(item) {
+            (_, true) => None,
+            (item, false) => Some(item),
+        })
+        .map(|item| match split_markdown_link(item) {
+            Some((title, url, rest)) => {
+                // Links to other notes, like Obsidian's [Note](Note.md), are just the note's name
+                let url = if is_web_link(url.trim()) { url } else { "" };
+                imported_option(
+                    &strip_wiki_links(title),
+                    &strip_wiki_links(
+                        rest.trim_start_matches(|c: char| c.is_whitespace() || "-–—:".contains(c)),
+                    ),
+                    url,
+                    "",
+                )
+            }
+            None => imported_option(&strip_wiki_links(item), "", "", ""),
+        })
+        .collect()
+}
+
+fn imported_option(
+    text: &str,
+    description: &str,
+    url: &str,
+    image_url: &str,
+) -> anyhow::Result<ImportedOption> {
+    let text = text.trim();
+    let details = OptionDetails::new(description, url, image_url)
+        .map_err(|e| anyhow!("\"{}\": {}", text, e))?;
+    Ok(ImportedOption::new(text.to_string(), details))
+}
+
+/// Spreadsheets and lists often leave the scheme off links, like `www.example.com`, so those get
+/// `https://`. Anything else that isn't a web link is dropped, keeping the option, like a Markdown
+/// link to a note.
+fn normalize_link(url: &str) -> String {
+    let url = url.trim();
+    if is_web_link(url) {
+        return url.to_string();
+    }
+    let with_scheme = format!("https://{}", url);
+    if url.to_ascii_lowercase().starts_with("www.") && is_web_link(&with_scheme) {
+        with_scheme
+    } else {
+        String::new()
+    }
+}
+
+/// The text of a Markdown list item, or `None` if the line isn't one
+fn strip_list_marker(line: &str) -> Option<&str> {
+    let line = line.trim_start();
+    for bullet in ["- ", "* ", "+ "] {
+        if let Some(item) = line.strip_prefix(bullet) {
+            return Some(item);
+        }
+    }
+
+    let digits = line.chars().take_while(char::is_ascii_digit).count();
+    if digits == 0 {
+        return None;
+    }
+    line[digits..]
+        .strip_prefix(". ")
+        .or_else(|| line[digits..].strip_prefix(") "))
+}
+
+/// Splits off a leading `[ ]` or `[x]` checkbox, returning whether it was ticked
+fn strip_checkbox(item: &str) -> (&str, bool) {
+    if let Some(item) = item.strip_prefix("[ ] ") {
+        (item, false)
+    } else if let Some(item) = item
+        .strip_prefix("[x] ")
+        .or_else(|| item.strip_prefix("[X] "))
+    {
+        (item, true)
+    } else {
+        (item, false)
+    }
+}
+
+/// Replaces Obsidian-style `[[Note]]` and `[[Note|Shown name]]` links with the name they show
+fn strip_wiki_links(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some((before, after)) = rest.split_once("[[") {
+        let Some((link, after)) = after.split_once("]]") else {
+            break;
+        };
+        stripped.push_str(before);
+        stripped.push_str(link.split_once('|').map_or(link, |(_, name)| name));
+        rest = after;
+    }
+    stripped.push_str(rest);
+    stripped
+}
+
+/// Splits `[title](url) rest` into its parts
+fn split_markdown_link(item: &str) -> Option<(&str, &str, &str)> {
+    let item = item.strip_prefix('[')?;
+    let (title, rest) = item.split_once("](")?;
+    let (url, rest) = rest.split_once(')')?;
+    Some((title, url, rest))
+}
+
+/// Spreadsheets copy as tab separated, so a tab in the header row means the rows are too
+fn csv_delimiter(header: &str) -> char {
+    if header.contains('\t') {
+        '\t'
+    } else {
+        ','
+    }
+}
+
+/// A header row has to have more than one column, so a lone "Name" line is read as an option
+fn is_csv_header(line: &str) -> bool {
+    line.contains([',', '\t']) && find_csv_column(&split_header(line), &TITLE_COLUMNS).is_some()
+}
+
+fn split_header(header: &str) -> Vec<String> {
+    header
+        .split(csv_delimiter(header))
+        .map(|column| column.trim().trim_matches('"').to_ascii_lowercase())
+        .collect()
+}
+
+fn find_csv_column(header: &[String], names: &[&str]) -> Option<usize> {
+    header
+        .iter()
+        .position(|column| names.contains(&column.as_str()))
+}
+
+/// Splits CSV text into records, handling quoted fields with delimiters, newlines and `""` escapes
+fn parse_csv_records(text: &str, delimiter: char) -> Vec<Vec<String>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            c if c == delimiter && !in_quotes => record.push(std::mem::take(&mut field)),
+            '\r' if !in_quotes => (),
+            '\n' if !in_quotes => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            c => field.push(c),
+        }
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+
+    records
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(options: &[ImportedOption]) -> Vec<&str> {
+        options.iter().map(|o| o.text.as_str()).collect()
+    }
+
+    #[test]
+    fn csv_fields_can_be_quoted() {
+        let records = parse_csv_records("a,\"b, c\",\"say \"\"hi\"\"\"\r\n\"two\nlines\",x\n", ',');
+
+        assert_eq!(
+            records,
+            vec![vec!["a", "b, c", "say \"hi\""], vec!["two\nlines", "x"],]
+        );
+    }
+
+    #[test]
+    fn csv_columns_are_found_by_name() {
+        let text = "Name,Notes,Link\nBowling,\"Lanes, shoes\",https://example.com\nPicnic,,\n";
+
+        let (format, options) = import_options(text).unwrap();
+
+        assert_eq!(format, ImportFormat::Csv);
+        assert_eq!(texts(&options), vec!["Bowling", "Picnic"]);
+        assert_eq!(options[0].details.description(), Some("Lanes, shoes"));
+        assert_eq!(options[0].details.url(), Some("https://example.com"));
+        assert!(options[1].details.is_empty());
+    }
+
+    #[test]
+    fn tab_separated_rows_are_csv() {
+        let (format, options) = import_options("Title\tDescription\nBowling\tLanes\n").unwrap();
+
+        assert_eq!(format, ImportFormat::Csv);
+        assert_eq!(options[0].details.description(), Some("Lanes"));
+    }
+
+    #[test]
+    fn a_lone_header_word_is_an_option() {
+        let (format, options) = import_options("Name\nBowling\nPicnic").unwrap();
+
+        assert_eq!(format, ImportFormat::Lines);
+        assert_eq!(texts(&options), vec!["Name", "Bowling", "Picnic"]);
+    }
+
+    #[test]
+    fn markdown_needs_most_lines_to_be_list_items() {
+        assert_eq!(
+            ImportFormat::detect("# Ideas\n\n- Bowling\n- Picnic\n"),
+            ImportFormat::Markdown
+        );
+        assert_eq!(
+            ImportFormat::detect("Bowling\nPicnic\n- Board games\n"),
+            ImportFormat::Lines
+        );
+        assert_eq!(
+            ImportFormat::detect("1. Bowling\n2) Picnic\n"),
+            ImportFormat::Markdown
+        );
+    }
+
+    #[test]
+    fn markdown_skips_ticked_checkboxes_and_keeps_links() {
+        let text = "- [ ] Bowling\n- [x] Picnic\n* [Cinema](https://example.com) - new releases\n";
+
+        let (_, options) = import_options(text).unwrap();
+
+        assert_eq!(texts(&options), vec!["Bowling", "Cinema"]);
+        assert_eq!(options[1].details.url(), Some("https://example.com"));
+        assert_eq!(options[1].details.description(), Some("new releases"));
+    }
+
+    #[test]
+    fn links_to_notes_are_just_names() {
+        let text =
+            "- [[Bowling]]\n- [Picnic](Picnic.md)\n- [[Cinema|The cinema]] - see [[Films]]\n";
+
+        let (_, options) = import_options(text).unwrap();
+
+        assert_eq!(
+            texts(&options),
+            vec!["Bowling", "Picnic", "The cinema - see Films"]
+        );
+        assert!(options[1].details.url().is_none());
+    }
+
+    #[test]
+    fn double_brackets_are_kept_outside_markdown() {
+        let (format, options) = import_options("[[Bowling]]\nPicnic\n").unwrap();
+
+        assert_eq!(format, ImportFormat::Lines);
+        assert_eq!(texts(&options), vec!["[[Bowling]]", "Picnic"]);
+
+        let (_, options) = import_options(r#"["[[Bowling]]"]"#).unwrap();
+
+        assert_eq!(texts(&options), vec!["[[Bowling]]"]);
+    }
+
+    #[test]
+    fn links_without_a_scheme_are_fixed_or_dropped() {
+        let text =
+            "Title,Link,Image\nBowling,www.example.com,\nPicnic,park,ftp://example.com/a.png\n";
+
+        let (_, options) = import_options(text).unwrap();
+
+        assert_eq!(texts(&options), vec!["Bowling", "Picnic"]);
+        assert_eq!(options[0].details.url(), Some("https://www.example.com"));
+        assert!(options[1].details.is_empty());
+
+        let (_, options) = import_options(r#"[{"title": "Cinema", "url": "cinema"}]"#).unwrap();
+
+        assert_eq!(texts(&options), vec!["Cinema"]);
+        assert!(options[0].details.url().is_none());
+    }
+
+    #[test]
+    fn json_takes_strings_and_objects() {
+        let text = r#"["Bowling", {"title": "Picnic", "url": "https://example.com"}]"#;
+
+        let (format, options) = import_options(text).unwrap();
+
+        assert_eq!(format, ImportFormat::Json);
+        assert_eq!(texts(&options), vec!["Bowling", "Picnic"]);
+        assert_eq!(options[1].details.url(), Some("https://example.com"));
+    }
+
+    #[test]
+    fn blank_and_repeated_lines_are_dropped() {
+        let (_, options) = import_options("Bowling\n\n  Picnic \nBowling\n").unwrap();
+
+        assert_eq!(texts(&options), vec!["Bowling", "Picnic"]);
+    }
+}
@@ -0,0 +1,1120 @@
+use std::{
+    collections::{HashMap, HashSet},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, bail};
+use itertools::Itertools;
+use linked_hash_map::LinkedHashMap;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{storage::writer::StorageWriter, BroadcastMsg, BroadcastSender, RoomEvent};
+
+use self::{
+    ballot::{Ballot, BallotKind, PartialBallotPolicy, MAX_SCORE},
+    import::ImportedOption,
+    option_details::OptionDetails,
+    participant::{Participant, ParticipantId, ParticipantStatus},
+    tally::{TallyMethodKind, TallyOutcome},
+    tie_break::TieBreakPolicy,
+    veto_completion::{
+        default_ready_quorum_percent, default_saved_veto_completion, required_ready_count,
+        VetoCompletion,
+    },
+    veto_rules::{default_veto_threshold, Veto},
+};
+
+pub mod ballot;
+pub mod import;
+pub mod moderation;
+pub mod option_details;
+pub mod participant;
+pub mod presence;
+pub mod snapshot;
+pub mod tally;
+/// Fixtures shared by the room state's tests
+#[cfg(test)]
+pub(crate) mod test_support;
+pub mod tie_break;
+pub mod veto_completion;
+pub mod veto_rules;
+
+const SPLIT_PATTERN: &str = "\n";
+const INVALID_VOTE_TEXT: &str = "INVALID VOTE";
+const UNKNOWN_PARTICIPANT_TEXT: &str = "someone";
+
+pub type Options = LinkedHashMap<Uuid, Option>;
+
+pub struct RoomState {
+    pub code: String,
+    options: Options,
+    participants: LinkedHashMap<ParticipantId, Participant>,
+    /// Can move the room on without waiting for everyone. `None` only for rooms saved before
+    /// participants were tracked, until someone joins.
+    host: std::option::Option<ParticipantId>,
+    /// Whether people who haven't joined yet are kept out
+    locked: bool,
+    /// People the host kicked out, who can't join again
+    kicked: HashSet<ParticipantId>,
+    /// Participants who have marked themselves done vetoing
+    ready: HashSet<ParticipantId>,
+    /// How many views each participant currently has open on the room
+    connections: HashMap<ParticipantId, usize>,
+    settings: RoomSettings,
+    voting_stage: VotingStage,
+    /// Each participant's latest ballot
+    votes: LinkedHashMap<ParticipantId, Ballot>,
+    broadcast_tx: BroadcastSender,
+    /// The sequence number of the last event sent to the room
+    event_seq: u64,
+    storage: StorageWriter,
+    last_activity: Instant,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RoomSettings {
+    pub tally_method: TallyMethodKind,
+    pub tie_break: TieBreakPolicy,
+    pub partial_ballots: PartialBallotPolicy,
+    /// Seed for `TieBreakPolicy::SeededRandom`, shown with the results so draws can be verified
+    pub tie_break_seed: u64,
+    #[serde(default = "default_saved_veto_completion")]
+    pub veto_completion: VetoCompletion,
+    /// Percentage of the room's participants who have to be ready before a
+    /// `VetoCompletion::ReadyCheck` room moves on
+    #[serde(default = "default_ready_quorum_percent")]
+    pub ready_quorum_percent: u8,
+    /// How many options each participant may veto. `None` means there's no limit.
+    #[serde(default)]
+    pub veto_limit: std::option::Option<u8>,
+    /// How many participants have to veto an option before it's struck
+    #[serde(default = "default_veto_threshold")]
+    pub veto_threshold: u8,
+}
+
+pub struct Option {
+    text: String,
+    details: OptionDetails,
+    pub vetoed: bool,
+    pub id: Uuid,
+    /// Who added the option. Unknown for options saved before participants were tracked.
+    pub added_by: std::option::Option<ParticipantId>,
+    /// Everyone who has vetoed the option, in the order they did. The option is only struck once
+    /// there are enough of them to reach the room's veto threshold.
+    pub vetoes: Vec<Veto>,
+}
+
+pub struct FinalVoteTally {
+    pub id: Uuid,
+    pub html_displayable_text: String,
+    pub score: usize,
+    pub ranks: Vec<usize>,
+    /// 1-based finishing place. Options that remain tied after tie-breaking share a place.
+    pub place: usize,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub enum VotingStage {
+    Vetoing,
+    Ranking,
+    Approving,
+    Scoring,
+    /// The host closed voting, so the results are final
+    Closed,
+}
+
+impl Default for RoomSettings {
+    fn default() -> Self {
+        Self {
+            tally_method: TallyMethodKind::default(),
+            tie_break: TieBreakPolicy::default(),
+            partial_ballots: PartialBallotPolicy::default(),
+            tie_break_seed: random_seed(),
+            veto_completion: VetoCompletion::default(),
+            ready_quorum_percent: default_ready_quorum_percent(),
+            veto_limit: None,
+            veto_threshold: default_veto_threshold(),
+        }
+    }
+}
+
+impl FinalVoteTally {
+    pub fn new(id: Uuid, html_displayable_text: String, score: usize, ranks: Vec<usize>) -> Self {
+        Self {
+            id,
+            html_displayable_text,
+            score,
+            ranks,
+            place: 0,
+        }
+    }
+}
+
+impl Option {
+    pub fn new(text: String, details: OptionDetails, added_by: ParticipantId) -> Self {
+        Self {
+            text,
+            details,
+            vetoed: false,
+            id: Uuid::new_v4(),
+            added_by: Some(added_by),
+            vetoes: Vec::new(),
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn get_html_text(&self) -> String {
+        ammonia::clean_text(&self.text)
+    }
+
+    pub fn details(&self) -> &OptionDetails {
+        &self.details
+    }
+}
+
+impl RoomState {
+    pub fn new(
+        code: String,
+        options: Vec<ImportedOption>,
+        settings: RoomSettings,
+        creator: Participant,
+        broadcast_tx: BroadcastSender,
+        storage: StorageWriter,
+    ) -> Self {
+        let options = build_options(options, creator.id);
+        let room = Self {
+            code,
+            options,
+            host: Some(creator.id),
+            locked: false,
+            kicked: HashSet::new(),
+            participants: LinkedHashMap::from_iter([(creator.id, creator)]),
+            ready: HashSet::new(),
+            connections: HashMap::new(),
+            settings,
+            voting_stage: VotingStage::Vetoing,
+            votes: LinkedHashMap::new(),
+            broadcast_tx,
+            event_seq: first_event_seq(),
+            storage,
+            last_activity: Instant::now(),
+        };
+        room.save();
+        room
+    }
+
+    pub fn get_broadcast_tx(&self) -> BroadcastSender {
+        self.broadcast_tx.clone()
+    }
+
+    /// Lets everyone watching the room know what happened
+    pub fn emit(&mut self, event: RoomEvent) {
+        self.event_seq += 1;
+        // Fails only if nobody is listening, which is fine
+        let _ = self.broadcast_tx.send(BroadcastMsg {
+            seq: self.event_seq,
+            event,
+        });
+    }
+
+    /// The sequence number of the last event sent to the room. Anyone who has seen it is up to date.
+    pub fn event_seq(&self) -> u64 {
+        self.event_seq
+    }
+
+    /// Adds someone to the room under the given nickname, or renames them if they've already joined
+    pub fn join(&mut self, id: ParticipantId, nickname: &str) -> anyhow::Result<()> {
+        let participant = Participant::new(id, nickname)?;
+        if self.kicked.contains(&id) {
+            bail!("The host removed you from this room, so you can't join it again");
+        }
+        if self.locked && !self.is_participant(&id) {
+            bail!("This room is locked, so nobody new can join");
+        }
+        if self
+            .participants
+            .values()
+            .any(|p| p.id != id && p.nickname().eq_ignore_ascii_case(participant.nickname()))
+        {
+            bail!(
+                "Someone in this room is already called \"{}\"",
+                participant.nickname()
+            );
+        }
+
+        self.participants.insert(id, participant);
+        self.host.get_or_insert(id);
+        self.changed();
+        self.emit(RoomEvent::ParticipantJoined { participant: id });
+        Ok(())
+    }
+
+    pub fn is_participant(&self, id: &ParticipantId) -> bool {
+        self.participants.contains_key(id)
+    }
+
+    pub fn iter_participants(&self) -> impl Iterator<Item = &Participant> {
+        self.participants.values()
+    }
+
+    /// Records another open view for the participant. Returns whether they just came online.
+    pub fn connect(&mut self, id: ParticipantId) -> bool {
+        let connections = self.connections.entry(id).or_insert(0);
+        *connections += 1;
+        let came_online = *connections == 1;
+        if came_online {
+            self.emit(RoomEvent::PresenceChanged {
+                participant: id,
+                online: true,
+            });
+        }
+        came_online
+    }
+
+    /// Records a closed view for the participant. Returns whether they just went offline.
+    pub fn disconnect(&mut self, id: ParticipantId) -> bool {
+        let went_offline = match self.connections.get_mut(&id) {
+            Some(connections) if *connections > 1 => {
+                *connections -= 1;
+                false
+            }
+            Some(_) => {
+                self.connections.remove(&id);
+                true
+            }
+            None => false,
+        };
+        if went_offline {
+            self.emit(RoomEvent::PresenceChanged {
+                participant: id,
+                online: false,
+            });
+        }
+        went_offline
+    }
+
+    pub fn is_online(&self, id: &ParticipantId) -> bool {
+        self.connections.contains_key(id)
+    }
+
+    pub fn host(&self) -> std::option::Option<ParticipantId> {
+        self.host
+    }
+
+    pub fn is_host(&self, id: &ParticipantId) -> bool {
+        self.host.as_ref() == Some(id)
+    }
+
+    pub fn get_participant_status(&self, id: &ParticipantId) -> ParticipantStatus {
+        if self.has_voted(id) {
+            ParticipantStatus::BallotSubmitted
+        } else if self.is_ready(id) || !matches!(self.voting_stage, VotingStage::Vetoing) {
+            ParticipantStatus::DoneVetoing
+        } else {
+            ParticipantStatus::Joined
+        }
+    }
+
+    /// Gets the participant's nickname as they entered it. Unlike `get_participant_html_nickname`,
+    /// this isn't safe to render as HTML.
+    pub fn get_participant_nickname(&self, id: &ParticipantId) -> String {
+        self.participants
+            .get(id)
+            .map_or(UNKNOWN_PARTICIPANT_TEXT.to_string(), |p| {
+                p.nickname().to_string()
+            })
+    }
+
+    pub fn get_participant_html_nickname(&self, id: &ParticipantId) -> String {
+        self.participants
+            .get(id)
+            .map_or(UNKNOWN_PARTICIPANT_TEXT.to_string(), |p| {
+                p.get_html_nickname()
+            })
+    }
+
+    /// Who vetoed the option and why, like `Ann ("too long"), Bob`
+    pub fn get_html_vetoes_text(&self, option: &Option) -> String {
+        option
+            .vetoes
+            .iter()
+            .map(|veto| {
+                let nickname = self.get_participant_html_nickname(&veto.by);
+                match veto.get_html_reason() {
+                    Some(reason) => format!("{} (\"{}\")", nickname, reason),
+                    None => nickname,
+                }
+            })
+            .join(", ")
+    }
+
+    pub fn add_option(
+        &mut self,
+        added_by: ParticipantId,
+        option: String,
+        details: OptionDetails,
+    ) -> anyhow::Result<()> {
+        self.ensure_participant(&added_by)?;
+        if !matches!(self.voting_stage, VotingStage::Vetoing) {
+            bail!("Options can only be added while vetoing");
+        }
+        let option = option.trim().to_string();
+        if valid_option(&option) && !self.options.iter().any(|(_, o)| o.text == option) {
+            let option = Option::new(option, details, added_by);
+            let id = option.id;
+            self.options.insert(id, option);
+            // Nobody should be moved on before they've had a chance to veto the new option
+            self.ready.clear();
+            self.changed();
+            self.emit(RoomEvent::OptionAdded {
+                option: id,
+                by: added_by,
+            });
+        }
+        Ok(())
+    }
+
+    /// Fixes an option's text, like a typo. Only whoever added the option or the host may do this.
+    pub fn rename_option(
+        &mut self,
+        by: ParticipantId,
+        id: Uuid,
+        text: String,
+    ) -> anyhow::Result<()> {
+        self.ensure_can_edit_option(&by, &id)?;
+        let text = text.trim().to_string();
+        if !valid_option(&text) {
+            bail!("Options can't be blank");
+        }
+        if self.options.values().any(|o| o.id != id && o.text == text) {
+            bail!("There's already an option called \"{}\"", text);
+        }
+        if let Some(option) = self.options.get_mut(&id) {
+            option.text = text;
+        }
+        // A renamed option may be one people would now veto, like a new one
+        self.ready.clear();
+        self.changed();
+        self.emit(RoomEvent::OptionRenamed { option: id, by });
+        Ok(())
+    }
+
+    /// Deletes an option, like a duplicate. Only whoever added the option or the host may do this.
+    pub fn remove_option(&mut self, by: ParticipantId, id: Uuid) -> anyhow::Result<()> {
+        self.ensure_can_edit_option(&by, &id)?;
+        self.options.remove(&id);
+        // People may have been ready only because of what was left to vote on
+        self.ready.clear();
+        self.changed();
+        self.emit(RoomEvent::OptionRemoved { option: id, by });
+        Ok(())
+    }
+
+    /// Whether the participant may rename or delete the option, which they can while vetoing if they
+    /// added it or are the host
+    pub fn can_edit_option(&self, by: &ParticipantId, id: &Uuid) -> bool {
+        matches!(self.voting_stage, VotingStage::Vetoing)
+            && self
+                .options
+                .get(id)
+                .is_some_and(|o| self.is_host(by) || o.added_by.as_ref() == Some(by))
+    }
+
+    pub fn contribute_votes(
+        &mut self,
+        voter: ParticipantId,
+        votes_text: String,
+    ) -> anyhow::Result<()> {
+        let votes = if !votes_text.is_empty() {
+            parse_votes(votes_text)?
+        } else {
+            self.get_default_ranking(&voter)
+        };
+        self.contribute_ballot(voter, Ballot::Ranked(votes))
+    }
+
+    pub fn contribute_ballot(
+        &mut self,
+        voter: ParticipantId,
+        ballot: Ballot,
+    ) -> anyhow::Result<()> {
+        self.ensure_participant(&voter)?;
+        self.validate_ballot(&ballot)?;
+        // Voting again replaces the voter's earlier ballot rather than adding to it
+        self.votes.insert(voter, ballot);
+        self.changed();
+        self.emit(RoomEvent::BallotSubmitted { voter });
+        Ok(())
+    }
+
+    pub fn settings(&self) -> &RoomSettings {
+        &self.settings
+    }
+
+    pub fn voting_stage(&self) -> VotingStage {
+        self.voting_stage.clone()
+    }
+
+    /// Whether the room is past vetoing and still accepting ballots
+    pub fn is_voting_open(&self) -> bool {
+        matches!(
+            self.voting_stage,
+            VotingStage::Ranking | VotingStage::Approving | VotingStage::Scoring
+        )
+    }
+
+    /// Moves everyone on to voting right away. Only the host may do this. Everyone else moves the room
+    /// on by marking themselves ready.
+    pub fn finish_vetoing(&mut self, by: ParticipantId) -> anyhow::Result<()> {
+        self.ensure_host(&by)?;
+        self.move_past_vetoing();
+        Ok(())
+    }
+
+    /// Marks the participant as done vetoing or not. Returns whether that moved the room on to voting,
+    /// which with `VetoCompletion::Immediate` happens as soon as anyone is ready. Does nothing once the
+    /// room is past vetoing.
+    pub fn mark_ready(&mut self, id: ParticipantId, ready: bool) -> anyhow::Result<bool> {
+        self.ensure_participant(&id)?;
+        if !matches!(self.voting_stage, VotingStage::Vetoing) {
+            return Ok(false);
+        }
+        if self.settings.veto_completion == VetoCompletion::Immediate {
+            if ready {
+                self.move_past_vetoing();
+            }
+            return Ok(ready);
+        }
+
+        if ready {
+            self.ready.insert(id);
+        } else {
+            self.ready.remove(&id);
+        }
+        self.changed();
+        self.emit(RoomEvent::ReadyChanged {
+            participant: id,
+            ready,
+        });
+        Ok(self.finish_vetoing_if_ready())
+    }
+
+    /// Moves the room on if it's waiting on a ready check that has reached its quorum, e.g. because
+    /// someone who wasn't ready was kicked. Returns whether the room moved on.
+    pub fn finish_vetoing_if_ready(&mut self) -> bool {
+        let waiting = matches!(self.voting_stage, VotingStage::Vetoing)
+            && self.settings.veto_completion == VetoCompletion::ReadyCheck;
+        if waiting && self.is_ready_quorum_reached() {
+            self.move_past_vetoing();
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn is_ready(&self, id: &ParticipantId) -> bool {
+        self.ready.contains(id)
+    }
+
+    pub fn ready_count(&self) -> usize {
+        self.ready.len()
+    }
+
+    /// How many people have to be ready for the room to move on. Everyone who joined counts, whether
+    /// or not they have the room open, since API clients are never online and a reload briefly takes
+    /// people offline. The host can kick anyone who wandered off, or move the room on themselves.
+    pub fn required_ready_count(&self) -> usize {
+        required_ready_count(self.settings.ready_quorum_percent, self.participants.len())
+    }
+
+    fn is_ready_quorum_reached(&self) -> bool {
+        self.ready_count() >= self.required_ready_count()
+    }
+
+    fn move_past_vetoing(&mut self) {
+        self.voting_stage = match self.settings.tally_method.ballot_kind() {
+            BallotKind::Ranked => VotingStage::Ranking,
+            BallotKind::Approval => VotingStage::Approving,
+            BallotKind::Score => VotingStage::Scoring,
+        };
+        self.changed();
+        self.emit(RoomEvent::FinishedVetoing);
+    }
+
+    /// Vetoes the option, optionally saying why. A blank reason is the same as giving none.
+    pub fn veto(&mut self, vetoed_by: ParticipantId, id: Uuid, reason: &str) -> anyhow::Result<()> {
+        self.ensure_participant(&vetoed_by)?;
+        if !matches!(self.voting_stage, VotingStage::Vetoing) {
+            bail!("Options can only be vetoed before voting starts");
+        }
+        let veto = Veto::new(vetoed_by, reason)?;
+        let remaining = self.get_remaining_vetoes(&vetoed_by);
+        let threshold = usize::from(self.settings.veto_threshold.max(1));
+        if let Some(o) = self.options.get_mut(&id) {
+            if o.vetoed || o.vetoes.iter().any(|v| v.by == vetoed_by) {
+                return Ok(());
+            }
+            if remaining == Some(0) {
+                bail!("You've used up all your vetoes");
+            }
+            o.vetoes.push(veto);
+            o.vetoed = o.vetoes.len() >= threshold;
+            self.changed();
+            self.emit(RoomEvent::OptionVetoed {
+                option: id,
+                by: vetoed_by,
+            });
+        }
+        Ok(())
+    }
+
+    /// Takes back `vetoer`'s veto of the option. Only they or the host may do this.
+    pub fn undo_veto(
+        &mut self,
+        by: ParticipantId,
+        id: Uuid,
+        vetoer: ParticipantId,
+    ) -> anyhow::Result<()> {
+        if by != vetoer && !self.is_host(&by) {
+            bail!("Only whoever vetoed that option or the host can undo the veto");
+        }
+        if !matches!(self.voting_stage, VotingStage::Vetoing) {
+            bail!("Vetoes can only be undone before voting starts");
+        }
+        let threshold = usize::from(self.settings.veto_threshold.max(1));
+        let option = self
+            .options
+            .get_mut(&id)
+            .ok_or_else(|| anyhow!("That option doesn't exist"))?;
+        let was_vetoed = option.vetoed;
+        option.vetoes.retain(|v| v.by != vetoer);
+        option.vetoed = option.vetoes.len() >= threshold;
+        if was_vetoed && !option.vetoed {
+            // Like a new option, nobody should be moved on before they've had a chance to veto it again
+            self.ready.clear();
+        }
+        self.changed();
+        self.emit(RoomEvent::VetoUndone {
+            option: id,
+            vetoer,
+            by,
+        });
+        Ok(())
+    }
+
+    /// How many more options the participant may veto, or `None` if the room has no veto limit
+    pub fn get_remaining_vetoes(&self, id: &ParticipantId) -> std::option::Option<usize> {
+        self.settings.veto_limit.map(|limit| {
+            let used = self
+                .options
+                .values()
+                .filter(|o| o.vetoes.iter().any(|v| v.by == *id))
+                .count();
+            usize::from(limit).saturating_sub(used)
+        })
+    }
+
+    pub fn has_vetoed(&self, by: &ParticipantId, id: &Uuid) -> bool {
+        self.options
+            .get(id)
+            .is_some_and(|o| o.vetoes.iter().any(|v| v.by == *by))
+    }
+
+    pub fn reset_all_vetos(&mut self, by: ParticipantId) -> anyhow::Result<()> {
+        self.ensure_host(&by)?;
+        for (_, option) in self.options.iter_mut() {
+            option.vetoed = false;
+            option.vetoes.clear();
+        }
+        self.changed();
+        self.emit(RoomEvent::VetosReset { by });
+        Ok(())
+    }
+
+    /// Marks the room as in use, postponing its expiry
+    pub fn touch(&mut self) {
+        self.last_activity = Instant::now();
+    }
+
+    pub fn is_idle_for(&self, ttl: Duration) -> bool {
+        self.last_activity.elapsed() >= ttl
+    }
+
+    fn ensure_participant(&self, id: &ParticipantId) -> anyhow::Result<()> {
+        if !self.is_participant(id) {
+            bail!("Join the room with a nickname first");
+        }
+        Ok(())
+    }
+
+    fn ensure_can_edit_option(&self, by: &ParticipantId, id: &Uuid) -> anyhow::Result<()> {
+        if !self.options.contains_key(id) {
+            bail!("That option isn't in this room");
+        }
+        if !matches!(self.voting_stage, VotingStage::Vetoing) {
+            bail!("Options can only be changed while vetoing");
+        }
+        if !self.can_edit_option(by, id) {
+            bail!("Only whoever added that option or the host can change it");
+        }
+        Ok(())
+    }
+
+    fn changed(&mut self) {
+        self.touch();
+        self.save();
+    }
+
+    fn save(&self) {
+        self.storage.save_room(self.snapshot());
+    }
+
+    pub fn iter_options(&self) -> impl Iterator<Item = &Option> {
+        self.options.values()
+    }
+
+    pub fn get_option(&self, id: &Uuid) -> std::option::Option<&Option> {
+        self.options.get(id)
+    }
+
+    /// Each ballot's voter alongside the ballot's lines
+    pub fn iter_html_displayable_votes(
+        &self,
+    ) -> impl Iterator<Item = (ParticipantId, Vec<String>)> + '_ {
+        self.votes
+            .iter()
+            .map(|(voter, ballot)| (*voter, ballot.html_displayable_lines(&self.options)))
+    }
+
+    /// Like `iter_html_displayable_votes`, but with the options' text as it was entered
+    pub fn iter_text_votes(&self) -> impl Iterator<Item = (ParticipantId, Vec<String>)> + '_ {
+        self.votes
+            .iter()
+            .map(|(voter, ballot)| (*voter, ballot.text_lines(&self.options)))
+    }
+
+    pub fn tally_votes(&self) -> TallyOutcome {
+        let ballots = self.ballots();
+        let mut outcome =
+            self.settings
+                .tally_method
+                .method()
+                .tally(&ballots, &self.options, &self.settings);
+        tie_break::break_ties(&mut outcome, &ballots, &self.settings);
+        outcome
+    }
+
+    fn ballots(&self) -> Vec<Ballot> {
+        self.votes.values().cloned().collect()
+    }
+
+    pub fn get_ballot(&self, voter: &ParticipantId) -> std::option::Option<&Ballot> {
+        self.votes.get(voter)
+    }
+
+    pub fn has_voted(&self, voter: &ParticipantId) -> bool {
+        self.votes.contains_key(voter)
+    }
+
+    /// The options still up for a vote, in the order the voter last ranked them. Options their earlier
+    /// ballot didn't rank, or that they haven't ranked at all yet, follow in the order they were added.
+    pub fn get_options_in_ranking_order(&self, voter: &ParticipantId) -> Vec<&Option> {
+        self.get_default_ranking(voter)
+            .iter()
+            .filter_map(|id| self.options.get(id))
+            .collect()
+    }
+
+    pub fn get_option_html_displayable_text(&self, id: &Uuid) -> String {
+        get_option_html_displayable_text(&self.options, id)
+    }
+
+    /// Gets the option's text as it was entered. Unlike `get_option_html_displayable_text`, this isn't
+    /// safe to render as HTML.
+    pub fn get_option_text(&self, id: &Uuid) -> String {
+        get_option_text(&self.options, id)
+    }
+
+    pub fn ballot_count(&self) -> usize {
+        self.votes.len()
+    }
+
+    /// Checks that a ballot only covers options that are still up for a vote, each at most once
+    fn validate_ballot(&self, ballot: &Ballot) -> anyhow::Result<()> {
+        if !self.is_voting_open() {
+            bail!("This room isn't accepting ballots");
+        }
+
+        let expected_kind = self.settings.tally_method.ballot_kind();
+        if ballot.kind() != expected_kind {
+            bail!(
+                "Expected a {:?} ballot, but received a {:?} ballot",
+                expected_kind,
+                ballot.kind()
+            );
+        }
+
+        let ids = ballot.option_ids();
+        if !ids.iter().all_unique() {
+            bail!("Ballot lists the same option more than once");
+        }
+
+        for id in ids.iter() {
+            let option = self
+                .options
+                .get(id)
+                .ok_or_else(|| anyhow!("Ballot contains an unknown option"))?;
+            if option.vetoed {
+                bail!("Ballot contains vetoed option \"{}\"", option.text);
+            }
+        }
+
+        if let Ballot::Score(scores) = ballot {
+            if scores.iter().any(|(_, score)| *score > MAX_SCORE) {
+                bail!("Scores must be between 0 and {}", MAX_SCORE);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn get_default_ranking(&self, voter: &ParticipantId) -> Vec<Uuid> {
+        let previous_ranking = match self.votes.get(voter) {
+            Some(Ballot::Ranked(ids)) => ids.clone(),
+            _ => Vec::new(),
+        };
+        let is_open = |id: &Uuid| self.options.get(id).is_some_and(|o| !o.vetoed);
+
+        previous_ranking
+            .iter()
+            .filter(|id| is_open(id))
+            .chain(
+                self.options
+                    .keys()
+                    .filter(|id| is_open(id) && !previous_ranking.contains(id)),
+            )
+            .cloned()
+            .collect()
+    }
+}
+
+pub(crate) fn get_option_html_displayable_text(options: &Options, id: &Uuid) -> String {
+    options
+        .get(id)
+        .map_or(INVALID_VOTE_TEXT.to_string(), |o| o.get_html_text())
+}
+
+pub(crate) fn get_option_text(options: &Options, id: &Uuid) -> String {
+    options
+        .get(id)
+        .map_or(INVALID_VOTE_TEXT.to_string(), |o| o.text.clone())
+}
+
+fn random_seed() -> u64 {
+    // Only the low 32 bits, so the seed stays short enough to read out loud
+    Uuid::new_v4().as_u64_pair().1 & u64::from(u32::MAX)
+}
+
+/// Where a room's event numbering starts. Starting from the current time in milliseconds rather than 0
+/// means a room reloaded after a restart doesn't reuse the ids of events sent before it, unless it
+/// averaged more than one event a millisecond.
+fn first_event_seq() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis() as u64)
+}
+
+fn parse_votes(votes_text: String) -> anyhow::Result<Vec<Uuid>> {
+    votes_text
+        .split(SPLIT_PATTERN)
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .map(|s| Uuid::parse_str(s).map_err(|_| anyhow!("\"{}\" is not a valid option id", s)))
+        .collect()
+}
+
+fn build_options(options: Vec<ImportedOption>, added_by: ParticipantId) -> Options {
+    options
+        .into_iter()
+        .filter(|o| valid_option(&o.text))
+        .unique_by(|o| o.text.clone())
+        .map(|o| Option::new(o.text, o.details, added_by))
+        .map(|o| (o.id, o))
+        .collect()
+}
+
+fn valid_option(option: &str) -> bool {
+    !option.is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::room_state::test_support::{join, room};
+
+    #[test]
+    fn veto_is_rejected_once_voting_starts() {
+        let (mut room, host, ids) = room(&["A", "B"], RoomSettings::default());
+        room.finish_vetoing(host).unwrap();
+
+        assert!(room.veto(host, ids[0], "").is_err());
+        assert!(!room.get_option(&ids[0]).unwrap().vetoed);
+    }
+
+    #[test]
+    fn vetoes_run_out_at_the_limit() {
+        let settings = RoomSettings {
+            veto_limit: Some(1),
+            ..RoomSettings::default()
+        };
+        let (mut room, host, ids) = room(&["A", "B", "C"], settings);
+        assert_eq!(room.get_remaining_vetoes(&host), Some(1));
+
+        room.veto(host, ids[0], "").unwrap();
+        assert_eq!(room.get_remaining_vetoes(&host), Some(0));
+        let error = room.veto(host, ids[1], "").unwrap_err();
+        assert_eq!(error.to_string(), "You've used up all your vetoes");
+        assert!(!room.get_option(&ids[1]).unwrap().vetoed);
+
+        room.undo_veto(host, ids[0], host).unwrap();
+        room.veto(host, ids[1], "").unwrap();
+        assert!(room.get_option(&ids[1]).unwrap().vetoed);
+    }
+
+    #[test]
+    fn options_are_only_struck_at_the_veto_threshold() {
+        let settings = RoomSettings {
+            veto_threshold: 2,
+            ..RoomSettings::default()
+        };
+        let (mut room, host, ids) = room(&["A", "B"], settings);
+        let ann = join(&mut room, "Ann");
+
+        room.veto(host, ids[0], "Too far").unwrap();
+        room.veto(host, ids[0], "").unwrap();
+        assert!(!room.get_option(&ids[0]).unwrap().vetoed);
+        assert_eq!(room.get_option(&ids[0]).unwrap().vetoes.len(), 1);
+
+        room.veto(ann, ids[0], "").unwrap();
+        assert!(room.get_option(&ids[0]).unwrap().vetoed);
+    }
+
+    #[test]
+    fn vetoes_can_be_undone_by_the_vetoer_or_the_host() {
+        let (mut room, host, ids) = room(&["A", "B"], RoomSettings::default());
+        let ann = join(&mut room, "Ann");
+        let bob = join(&mut room, "Bob");
+        room.veto(ann, ids[0], "").unwrap();
+        room.veto(ann, ids[1], "").unwrap();
+
+        assert!(room.undo_veto(bob, ids[0], ann).is_err());
+        assert!(room.has_vetoed(&ann, &ids[0]));
+
+        room.undo_veto(ann, ids[0], ann).unwrap();
+        assert!(!room.has_vetoed(&ann, &ids[0]));
+        assert!(!room.get_option(&ids[0]).unwrap().vetoed);
+        room.undo_veto(host, ids[1], ann).unwrap();
+        assert!(!room.has_vetoed(&ann, &ids[1]));
+    }
+
+    #[test]
+    fn vetoes_cant_be_undone_once_voting_starts() {
+        let (mut room, host, ids) = room(&["A", "B"], RoomSettings::default());
+        room.veto(host, ids[0], "").unwrap();
+        room.finish_vetoing(host).unwrap();
+
+        assert!(room.undo_veto(host, ids[0], host).is_err());
+        assert!(room.get_option(&ids[0]).unwrap().vetoed);
+    }
+
+    #[test]
+    fn undoing_a_strike_clears_ready_marks() {
+        let (mut room, host, ids) = room(&["A", "B"], RoomSettings::default());
+        let ann = join(&mut room, "Ann");
+        room.veto(ann, ids[0], "").unwrap();
+        room.mark_ready(ann, true).unwrap();
+
+        room.undo_veto(host, ids[0], ann).unwrap();
+
+        assert!(!room.is_ready(&ann));
+    }
+
+    #[test]
+    fn options_can_be_edited_by_their_author_or_the_host() {
+        let (mut room, host, ids) = room(&["A"], RoomSettings::default());
+        let ann = join(&mut room, "Ann");
+        let bob = join(&mut room, "Bob");
+        room.add_option(ann, "B".to_string(), OptionDetails::default())
+            .unwrap();
+        let added = room.iter_options().find(|o| o.text() == "B").unwrap().id;
+
+        assert!(room.rename_option(bob, added, "C".to_string()).is_err());
+        assert!(room.remove_option(bob, added).is_err());
+        assert!(room.rename_option(ann, ids[0], "C".to_string()).is_err());
+
+        room.rename_option(ann, added, " C ".to_string()).unwrap();
+        assert_eq!(room.get_option(&added).unwrap().text(), "C");
+        assert!(room.rename_option(ann, added, "A".to_string()).is_err());
+        room.rename_option(host, ids[0], "D".to_string()).unwrap();
+        room.remove_option(host, added).unwrap();
+        assert!(room.get_option(&added).is_none());
+    }
+
+    #[test]
+    fn options_cant_be_edited_once_voting_starts() {
+        let (mut room, host, ids) = room(&["A", "B"], RoomSettings::default());
+        room.finish_vetoing(host).unwrap();
+
+        assert!(!room.can_edit_option(&host, &ids[0]));
+        assert!(room.rename_option(host, ids[0], "C".to_string()).is_err());
+        assert!(room.remove_option(host, ids[0]).is_err());
+    }
+
+    #[test]
+    fn editing_options_clears_ready_marks() {
+        let (mut room, host, ids) = room(&["A", "B", "C"], RoomSettings::default());
+        let ann = join(&mut room, "Ann");
+
+        room.mark_ready(ann, true).unwrap();
+        room.rename_option(host, ids[0], "D".to_string()).unwrap();
+        assert!(!room.is_ready(&ann));
+
+        room.mark_ready(ann, true).unwrap();
+        room.remove_option(host, ids[1]).unwrap();
+        assert!(!room.is_ready(&ann));
+    }
+
+    #[test]
+    fn participants_stay_online_until_their_last_view_closes() {
+        let (mut room, host, _) = room(&["A"], RoomSettings::default());
+
+        assert!(room.connect(host));
+        assert!(!room.connect(host));
+        assert!(!room.disconnect(host));
+        assert!(room.is_online(&host));
+        assert!(room.disconnect(host));
+        assert!(!room.is_online(&host));
+        assert!(!room.disconnect(host));
+    }
+
+    #[test]
+    fn participant_status_follows_the_room_through_voting() {
+        let (mut room, host, ids) = room(&["A", "B"], RoomSettings::default());
+        let ann = join(&mut room, "Ann");
+        let status = |room: &RoomState, id| room.get_participant_status(id);
+
+        assert!(matches!(status(&room, &host), ParticipantStatus::Joined));
+        room.mark_ready(ann, true).unwrap();
+        assert!(matches!(
+            status(&room, &ann),
+            ParticipantStatus::DoneVetoing
+        ));
+
+        room.finish_vetoing(host).unwrap();
+        assert!(matches!(
+            status(&room, &host),
+            ParticipantStatus::DoneVetoing
+        ));
+        room.contribute_ballot(host, Ballot::Ranked(vec![ids[0], ids[1]]))
+            .unwrap();
+        assert!(matches!(
+            status(&room, &host),
+            ParticipantStatus::BallotSubmitted
+        ));
+    }
+
+    #[test]
+    fn ready_check_moves_on_once_the_quorum_is_ready() {
+        let settings = RoomSettings {
+            ready_quorum_percent: 50,
+            ..RoomSettings::default()
+        };
+        let (mut room, host, _) = room(&["A", "B"], settings);
+        let ann = join(&mut room, "Ann");
+        let bob = join(&mut room, "Bob");
+        assert_eq!(room.required_ready_count(), 2);
+
+        assert!(!room.mark_ready(ann, true).unwrap());
+        assert!(!room.mark_ready(ann, false).unwrap());
+        assert!(!room.mark_ready(host, true).unwrap());
+        assert!(matches!(room.voting_stage(), VotingStage::Vetoing));
+
+        assert!(room.mark_ready(bob, true).unwrap());
+        assert!(matches!(room.voting_stage(), VotingStage::Ranking));
+    }
+
+    #[test]
+    fn immediate_completion_moves_on_when_anyone_is_ready() {
+        let settings = RoomSettings {
+            veto_completion: VetoCompletion::Immediate,
+            ..RoomSettings::default()
+        };
+        let (mut room, _, _) = room(&["A", "B"], settings);
+        let ann = join(&mut room, "Ann");
+
+        assert!(!room.mark_ready(ann, false).unwrap());
+        assert!(matches!(room.voting_stage(), VotingStage::Vetoing));
+        assert!(room.mark_ready(ann, true).unwrap());
+        assert!(matches!(room.voting_stage(), VotingStage::Ranking));
+    }
+
+    #[test]
+    fn ready_marks_are_ignored_once_vetoing_is_over() {
+        let (mut room, host, _) = room(&["A", "B"], RoomSettings::default());
+        room.finish_vetoing(host).unwrap();
+
+        assert!(!room.mark_ready(host, true).unwrap());
+        assert!(!room.is_ready(&host));
+    }
+
+    #[test]
+    fn ballots_only_cover_open_options_once_each() {
+        let (mut room, host, ids) = room(&["A", "B", "C"], RoomSettings::default());
+        room.veto(host, ids[2], "").unwrap();
+        room.finish_vetoing(host).unwrap();
+
+        let repeated = Ballot::Ranked(vec![ids[0], ids[0]]);
+        assert!(room.contribute_ballot(host, repeated).is_err());
+        let unknown = Ballot::Ranked(vec![ids[0], Uuid::new_v4()]);
+        assert!(room.contribute_ballot(host, unknown).is_err());
+        let vetoed = Ballot::Ranked(vec![ids[0], ids[2]]);
+        assert!(room.contribute_ballot(host, vetoed).is_err());
+        let wrong_kind = Ballot::Approval(vec![ids[0]]);
+        assert!(room.contribute_ballot(host, wrong_kind).is_err());
+        assert!(!room.has_voted(&host));
+
+        room.contribute_ballot(host, Ballot::Ranked(vec![ids[1], ids[0]]))
+            .unwrap();
+        assert!(room.has_voted(&host));
+    }
+
+    #[test]
+    fn ballots_are_only_taken_while_voting_is_open() {
+        let (mut room, host, ids) = room(&["A", "B"], RoomSettings::default());
+        let ballot = Ballot::Ranked(vec![ids[0], ids[1]]);
+
+        assert!(room.contribute_ballot(host, ballot.clone()).is_err());
+        room.finish_vetoing(host).unwrap();
+        room.close_voting(host).unwrap();
+        assert!(room.contribute_ballot(host, ballot).is_err());
+    }
+
+    #[test]
+    fn scores_above_the_maximum_are_rejected() {
+        let settings = RoomSettings {
+            tally_method: TallyMethodKind::Star,
+            ..RoomSettings::default()
+        };
+        let (mut room, host, ids) = room(&["A", "B"], settings);
+        room.finish_vetoing(host).unwrap();
+
+        let too_high = Ballot::Score(vec![(ids[0], MAX_SCORE + 1), (ids[1], 0)]);
+        assert!(room.contribute_ballot(host, too_high).is_err());
+        let highest = Ballot::Score(vec![(ids[0], MAX_SCORE), (ids[1], 0)]);
+        room.contribute_ballot(host, highest).unwrap();
+    }
+}
@@ -0,0 +1,142 @@
+use anyhow::{anyhow, bail};
+
+use crate::RoomEvent;
+
+use super::{participant::ParticipantId, RoomState, VotingStage};
+
+/// Things only the room's host may do
+impl RoomState {
+    pub fn transfer_host(&mut self, by: ParticipantId, to: ParticipantId) -> anyhow::Result<()> {
+        self.ensure_host(&by)?;
+        self.ensure_participant(&to)?;
+        self.host = Some(to);
+        self.changed();
+        self.emit(RoomEvent::HostChanged { host: to });
+        Ok(())
+    }
+
+    /// Removes someone from the room along with their ballot. The options they added and their vetoes
+    /// stay. They can't join again as the same person, but only locking the room keeps them from coming
+    /// back with a new session.
+    pub fn kick(&mut self, by: ParticipantId, id: ParticipantId) -> anyhow::Result<()> {
+        self.ensure_host(&by)?;
+        if by == id {
+            bail!("The host can't kick themselves. Make someone else host first.");
+        }
+        self.participants
+            .remove(&id)
+            .ok_or_else(|| anyhow!("That person isn't in this room"))?;
+        self.ready.remove(&id);
+        self.votes.remove(&id);
+        self.kicked.insert(id);
+        self.changed();
+        self.emit(RoomEvent::ParticipantKicked { participant: id });
+        // Everyone left may be ready now
+        self.finish_vetoing_if_ready();
+        Ok(())
+    }
+
+    /// Stops (or lets) people who haven't joined yet join the room
+    pub fn set_locked(&mut self, by: ParticipantId, locked: bool) -> anyhow::Result<()> {
+        self.ensure_host(&by)?;
+        self.locked = locked;
+        self.changed();
+        self.emit(RoomEvent::LockChanged { locked });
+        Ok(())
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.locked
+    }
+
+    /// Stops accepting ballots, so the current results are final
+    pub fn close_voting(&mut self, by: ParticipantId) -> anyhow::Result<()> {
+        self.ensure_host(&by)?;
+        if !self.is_voting_open() {
+            bail!("Voting isn't open");
+        }
+        self.voting_stage = VotingStage::Closed;
+        self.changed();
+        self.emit(RoomEvent::VotingClosed);
+        Ok(())
+    }
+
+    pub(super) fn ensure_host(&self, id: &ParticipantId) -> anyhow::Result<()> {
+        if !self.is_host(id) {
+            bail!("Only the host can do that");
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::room_state::{
+        ballot::Ballot,
+        participant::ParticipantId,
+        test_support::{join, room},
+        RoomSettings, VotingStage,
+    };
+
+    #[test]
+    fn kicked_participants_lose_their_ballot_and_cant_rejoin() {
+        let (mut room, host, ids) = room(&["A", "B"], RoomSettings::default());
+        let ann = join(&mut room, "Ann");
+        room.finish_vetoing(host).unwrap();
+        room.contribute_ballot(ann, Ballot::Ranked(vec![ids[0], ids[1]]))
+            .unwrap();
+
+        room.kick(host, ann).unwrap();
+
+        assert!(!room.is_participant(&ann));
+        assert_eq!(room.ballot_count(), 0);
+        assert!(room.join(ann, "Ann").is_err());
+        assert!(room.join(ParticipantId::new(), "Ann").is_ok());
+    }
+
+    #[test]
+    fn kicking_the_last_holdout_moves_the_room_on() {
+        let (mut room, host, _) = room(&["A", "B"], RoomSettings::default());
+        let ann = join(&mut room, "Ann");
+        room.mark_ready(host, true).unwrap();
+
+        room.kick(host, ann).unwrap();
+
+        assert!(matches!(room.voting_stage(), VotingStage::Ranking));
+    }
+
+    #[test]
+    fn only_the_host_can_kick_and_not_themselves() {
+        let (mut room, host, _) = room(&["A"], RoomSettings::default());
+        let ann = join(&mut room, "Ann");
+
+        assert!(room.kick(ann, host).is_err());
+        assert!(room.kick(host, host).is_err());
+        assert!(room.is_participant(&host));
+        assert!(room.is_participant(&ann));
+    }
+
+    #[test]
+    fn locked_rooms_only_let_existing_participants_rejoin() {
+        let (mut room, host, _) = room(&["A"], RoomSettings::default());
+        let ann = join(&mut room, "Ann");
+
+        assert!(room.set_locked(ann, true).is_err());
+        room.set_locked(host, true).unwrap();
+
+        assert!(room.join(ParticipantId::new(), "Bob").is_err());
+        assert!(room.join(ann, "Annie").is_ok());
+    }
+
+    #[test]
+    fn hosting_can_be_handed_to_another_participant() {
+        let (mut room, host, _) = room(&["A"], RoomSettings::default());
+        let ann = join(&mut room, "Ann");
+
+        assert!(room.transfer_host(host, ParticipantId::new()).is_err());
+        room.transfer_host(host, ann).unwrap();
+
+        assert!(room.is_host(&ann));
+        assert!(room.transfer_host(host, host).is_err());
+    }
+}
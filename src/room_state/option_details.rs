@@ -0,0 +1,86 @@
+use anyhow::bail;
+use serde::{Deserialize, Serialize};
+
+pub const MAX_DESCRIPTION_LENGTH: usize = 500;
+pub const MAX_URL_LENGTH: usize = 2000;
+
+/// Optional extras that help people decide on an option, like a blurb, a trailer or a store page
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct OptionDetails {
+    description: Option<String>,
+    url: Option<String>,
+    image_url: Option<String>,
+}
+
+impl OptionDetails {
+    /// Blank fields are left out. Links have to be http(s) so they can't run scripts when clicked.
+    pub fn new(description: &str, url: &str, image_url: &str) -> anyhow::Result<Self> {
+        Ok(Self {
+            description: validate_description(description)?,
+            url: validate_url(url, "Links")?,
+            image_url: validate_url(image_url, "Image links")?,
+        })
+    }
+
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    pub fn url(&self) -> Option<&str> {
+        self.url.as_deref()
+    }
+
+    pub fn image_url(&self) -> Option<&str> {
+        self.image_url.as_deref()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.description.is_none() && self.url.is_none() && self.image_url.is_none()
+    }
+
+    pub fn get_html_description(&self) -> Option<String> {
+        self.description.as_deref().map(ammonia::clean_text)
+    }
+
+    pub fn get_html_url(&self) -> Option<String> {
+        self.url.as_deref().map(ammonia::clean_text)
+    }
+
+    pub fn get_html_image_url(&self) -> Option<String> {
+        self.image_url.as_deref().map(ammonia::clean_text)
+    }
+}
+
+fn validate_description(description: &str) -> anyhow::Result<Option<String>> {
+    let description = description.trim();
+    if description.chars().count() > MAX_DESCRIPTION_LENGTH {
+        bail!(
+            "Descriptions can be at most {} characters long",
+            MAX_DESCRIPTION_LENGTH
+        );
+    }
+
+    Ok((!description.is_empty()).then(|| description.to_string()))
+}
+
+fn validate_url(url: &str, name: &str) -> anyhow::Result<Option<String>> {
+    let url = url.trim();
+    if url.is_empty() {
+        return Ok(None);
+    }
+    if url.len() > MAX_URL_LENGTH {
+        bail!("{} can be at most {} characters long", name, MAX_URL_LENGTH);
+    }
+    if !is_web_link(url) {
+        bail!("{} have to start with http:// or https://", name);
+    }
+
+    Ok(Some(url.to_string()))
+}
+
+/// Whether the link is one we'll show: http(s), with no whitespace in it
+pub fn is_web_link(url: &str) -> bool {
+    let lowercase = url.to_ascii_lowercase();
+    (lowercase.starts_with("http://") || lowercase.starts_with("https://"))
+        && !url.chars().any(char::is_whitespace)
+}
@@ -0,0 +1,94 @@
+use std::{fmt, str::FromStr};
+
+use anyhow::bail;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+pub const MAX_NICKNAME_LENGTH: usize = 24;
+
+/// Identifies a person across rooms, and is shown to everyone in them. The server maps their session
+/// cookie to it, so it survives refreshes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ParticipantId(Uuid);
+
+/// How far along someone is, as shown in the room's roster
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParticipantStatus {
+    Joined,
+    DoneVetoing,
+    BallotSubmitted,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Participant {
+    pub id: ParticipantId,
+    nickname: String,
+}
+
+impl ParticipantId {
+    pub fn new() -> Self {
+        Self(Uuid::new_v4())
+    }
+}
+
+impl Default for ParticipantId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Display for ParticipantId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0.as_simple())
+    }
+}
+
+impl FromStr for ParticipantId {
+    type Err = uuid::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self(Uuid::parse_str(s)?))
+    }
+}
+
+impl ParticipantStatus {
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            ParticipantStatus::Joined => "joined",
+            ParticipantStatus::DoneVetoing => "done vetoing",
+            ParticipantStatus::BallotSubmitted => "ballot submitted",
+        }
+    }
+}
+
+impl Participant {
+    pub fn new(id: ParticipantId, nickname: &str) -> anyhow::Result<Self> {
+        Ok(Self {
+            id,
+            nickname: validate_nickname(nickname)?,
+        })
+    }
+
+    pub fn nickname(&self) -> &str {
+        &self.nickname
+    }
+
+    pub fn get_html_nickname(&self) -> String {
+        ammonia::clean_text(&self.nickname)
+    }
+}
+
+fn validate_nickname(nickname: &str) -> anyhow::Result<String> {
+    let nickname = nickname.trim();
+    if nickname.is_empty() {
+        bail!("Please enter a nickname");
+    }
+    if nickname.chars().count() > MAX_NICKNAME_LENGTH {
+        bail!(
+            "Nicknames can be at most {} characters long",
+            MAX_NICKNAME_LENGTH
+        );
+    }
+
+    Ok(nickname.to_string())
+}
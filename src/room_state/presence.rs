@@ -0,0 +1,32 @@
+use std::sync::{Arc, RwLock};
+
+use super::{participant::ParticipantId, RoomState};
+
+/// Counts a connected view as present in a room for as long as it's held, letting the room know
+/// whenever someone comes online or goes offline
+pub struct PresenceGuard {
+    room_state: Arc<RwLock<RoomState>>,
+    participant: ParticipantId,
+}
+
+impl PresenceGuard {
+    pub fn new(room_state: Arc<RwLock<RoomState>>, participant: ParticipantId) -> Self {
+        room_state.write().unwrap().connect(participant);
+        Self {
+            room_state,
+            participant,
+        }
+    }
+
+    pub fn is_for(&self, room_state: &Arc<RwLock<RoomState>>) -> bool {
+        Arc::ptr_eq(&self.room_state, room_state)
+    }
+}
+
+impl Drop for PresenceGuard {
+    fn drop(&mut self) {
+        if let Ok(mut room) = self.room_state.write() {
+            room.disconnect(self.participant);
+        }
+    }
+}
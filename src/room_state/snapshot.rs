@@ -0,0 +1,134 @@
+use std::{
+    collections::{HashMap, HashSet},
+    time::Instant,
+};
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{storage::writer::StorageWriter, BroadcastSender};
+
+use super::{
+    ballot::Ballot,
+    first_event_seq,
+    option_details::OptionDetails,
+    participant::{Participant, ParticipantId},
+    veto_rules::Veto,
+    Option, RoomSettings, RoomState, VotingStage,
+};
+
+/// Everything about a room worth keeping across restarts
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RoomSnapshot {
+    pub code: String,
+    pub settings: RoomSettings,
+    pub voting_stage: VotingStage,
+    pub options: Vec<OptionSnapshot>,
+    pub participants: Vec<Participant>,
+    pub host: std::option::Option<ParticipantId>,
+    pub locked: bool,
+    pub kicked: Vec<ParticipantId>,
+    /// Participants who have marked themselves done vetoing
+    pub ready: Vec<ParticipantId>,
+    pub ballots: Vec<BallotSnapshot>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct OptionSnapshot {
+    pub id: Uuid,
+    pub text: String,
+    #[serde(default)]
+    pub details: OptionDetails,
+    pub vetoed: bool,
+    pub added_by: std::option::Option<ParticipantId>,
+    pub vetoes: Vec<Veto>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct BallotSnapshot {
+    pub voter: ParticipantId,
+    pub ballot: Ballot,
+}
+
+impl RoomState {
+    pub fn snapshot(&self) -> RoomSnapshot {
+        RoomSnapshot {
+            code: self.code.clone(),
+            settings: self.settings.clone(),
+            voting_stage: self.voting_stage.clone(),
+            options: self
+                .options
+                .values()
+                .map(|o| OptionSnapshot {
+                    id: o.id,
+                    text: o.text.clone(),
+                    details: o.details.clone(),
+                    vetoed: o.vetoed,
+                    added_by: o.added_by,
+                    vetoes: o.vetoes.clone(),
+                })
+                .collect(),
+            participants: self.participants.values().cloned().collect(),
+            host: self.host,
+            locked: self.locked,
+            kicked: self.kicked.iter().cloned().collect(),
+            ready: self.ready.iter().cloned().collect(),
+            ballots: self
+                .votes
+                .iter()
+                .map(|(voter, ballot)| BallotSnapshot {
+                    voter: *voter,
+                    ballot: ballot.clone(),
+                })
+                .collect(),
+        }
+    }
+
+    pub fn from_snapshot(
+        snapshot: RoomSnapshot,
+        broadcast_tx: BroadcastSender,
+        storage: StorageWriter,
+    ) -> Self {
+        Self {
+            code: snapshot.code,
+            options: snapshot
+                .options
+                .into_iter()
+                .map(|o| {
+                    (
+                        o.id,
+                        Option {
+                            text: o.text,
+                            details: o.details,
+                            vetoed: o.vetoed,
+                            id: o.id,
+                            added_by: o.added_by,
+                            vetoes: o.vetoes,
+                        },
+                    )
+                })
+                .collect(),
+            participants: snapshot
+                .participants
+                .into_iter()
+                .map(|p| (p.id, p))
+                .collect(),
+            host: snapshot.host,
+            locked: snapshot.locked,
+            kicked: HashSet::from_iter(snapshot.kicked),
+            ready: HashSet::from_iter(snapshot.ready),
+            connections: HashMap::new(),
+            settings: snapshot.settings,
+            voting_stage: snapshot.voting_stage,
+            votes: snapshot
+                .ballots
+                .into_iter()
+                .map(|b| (b.voter, b.ballot))
+                .collect(),
+            broadcast_tx,
+            event_seq: first_event_seq(),
+            storage,
+            last_activity: Instant::now(),
+        }
+    }
+}
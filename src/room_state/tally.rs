@@ -0,0 +1,851 @@
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::{
+    ballot::{Ballot, BallotKind, PartialBallotPolicy, MAX_SCORE},
+    get_option_html_displayable_text,
+    tie_break::{order_tied, TieBreak, TieBreakPolicy},
+    FinalVoteTally, Options, RoomSettings,
+};
+
+pub trait TallyMethod {
+    fn tally(&self, ballots: &[Ballot], options: &Options, settings: &RoomSettings)
+        -> TallyOutcome;
+}
+
+pub struct TallyOutcome {
+    pub tallies: Vec<FinalVoteTally>,
+    pub breakdown: TallyBreakdown,
+    /// Groups of options the method itself couldn't put in order, each next to each other in
+    /// `tallies`. These are the only ties the room's tie-break policy gets to settle.
+    pub undecided: Vec<Vec<Uuid>>,
+    pub tie_breaks: Vec<TieBreak>,
+}
+
+impl TallyOutcome {
+    fn new(
+        tallies: Vec<FinalVoteTally>,
+        breakdown: TallyBreakdown,
+        undecided: Vec<Vec<Uuid>>,
+    ) -> Self {
+        Self {
+            tallies,
+            breakdown,
+            undecided,
+            tie_breaks: Vec::new(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub enum TallyBreakdown {
+    None,
+    InstantRunoff(Vec<RunoffRound>),
+    Pairwise(PairwiseMatrix),
+    StarRunoff(StarRunoff),
+}
+
+#[derive(Serialize)]
+pub struct RunoffRound {
+    /// First-preference counts of every option still in the running, highest first
+    pub counts: Vec<(Uuid, usize)>,
+    /// `None` in the last round, once counting finished
+    pub eliminated: Option<Uuid>,
+    /// How the eliminated option was picked, when several shared the fewest votes
+    pub elimination_tie: Option<EliminationTie>,
+    /// Where the eliminated option's votes went. A destination of `None` means the ballots were exhausted.
+    pub transfers: Vec<(Option<Uuid>, usize)>,
+}
+
+#[derive(Serialize)]
+pub struct EliminationTie {
+    pub tied: Vec<Uuid>,
+    pub decided_by: EliminationTieBreak,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum EliminationTieBreak {
+    /// The option with the fewest votes in the latest earlier round where the counts differed
+    EarlierRound,
+    Policy(TieBreakPolicy),
+    /// Nothing else separated them, so the last in option order went
+    OptionOrder,
+}
+
+#[derive(Serialize)]
+pub struct PairwiseMatrix {
+    pub options: Vec<Uuid>,
+    /// `preferences[i][j]` is the number of voters preferring `options[i]` over `options[j]`
+    pub preferences: Vec<Vec<usize>>,
+    pub condorcet_winner: Option<Uuid>,
+}
+
+/// The automatic runoff between the two highest-scoring options in STAR voting
+#[derive(Serialize)]
+pub struct StarRunoff {
+    pub finalists: (Uuid, Uuid),
+    /// How many ballots scored each finalist above the other
+    pub preferred_counts: (usize, usize),
+    pub no_preference: usize,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TallyMethodKind {
+    #[default]
+    Borda,
+    InstantRunoff,
+    Schulze,
+    Approval,
+    Star,
+}
+
+impl TallyMethodKind {
+    pub const ALL: [TallyMethodKind; 5] = [
+        TallyMethodKind::Borda,
+        TallyMethodKind::InstantRunoff,
+        TallyMethodKind::Schulze,
+        TallyMethodKind::Approval,
+        TallyMethodKind::Star,
+    ];
+
+    pub fn ballot_kind(&self) -> BallotKind {
+        match self {
+            TallyMethodKind::Borda | TallyMethodKind::InstantRunoff | TallyMethodKind::Schulze => {
+                BallotKind::Ranked
+            }
+            TallyMethodKind::Approval => BallotKind::Approval,
+            TallyMethodKind::Star => BallotKind::Score,
+        }
+    }
+
+    pub fn method(&self) -> Box<dyn TallyMethod + Send + Sync> {
+        match self {
+            TallyMethodKind::Borda => Box::new(BordaCount),
+            TallyMethodKind::InstantRunoff => Box::new(InstantRunoff),
+            TallyMethodKind::Schulze => Box::new(Schulze),
+            TallyMethodKind::Approval => Box::new(ApprovalCount),
+            TallyMethodKind::Star => Box::new(Star),
+        }
+    }
+
+    pub fn form_value(&self) -> &'static str {
+        match self {
+            TallyMethodKind::Borda => "Borda",
+            TallyMethodKind::InstantRunoff => "InstantRunoff",
+            TallyMethodKind::Schulze => "Schulze",
+            TallyMethodKind::Approval => "Approval",
+            TallyMethodKind::Star => "Star",
+        }
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            TallyMethodKind::Borda => "Borda count",
+            TallyMethodKind::InstantRunoff => "Instant-runoff",
+            TallyMethodKind::Schulze => "Schulze (Condorcet)",
+            TallyMethodKind::Approval => "Approval",
+            TallyMethodKind::Star => "STAR (score, then automatic runoff)",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            TallyMethodKind::Borda => "A score is calculated for each option, weighted by the rankings it received from each voter.",
+            TallyMethodKind::InstantRunoff => "Each ballot counts for its highest-ranked option still in the running. The option with the fewest votes is eliminated each round and its votes move to the next choice on those ballots, until one option holds a majority. The score is the number of votes an option held in its last round.",
+            TallyMethodKind::Schulze => "Every pair of options is compared head-to-head across all ballots. An option that beats every other option head-to-head is the Condorcet winner. Otherwise, the Schulze method compares the strongest chains of head-to-head wins between options. The score is the number of options each option beats this way.",
+            TallyMethodKind::Approval => "Each voter ticks every option they'd be happy to do. The score is the number of voters who approved of an option.",
+            TallyMethodKind::Star => "Each voter scores every option from 0 to 5. The two options with the highest total scores go to an automatic runoff, which is won by the finalist scored higher on more ballots. The score is an option's total score.",
+        }
+    }
+}
+
+pub struct BordaCount;
+
+pub struct InstantRunoff;
+
+pub struct Schulze;
+
+pub struct ApprovalCount;
+
+pub struct Star;
+
+struct VoteTally {
+    id: Uuid,
+    html_displayable_text: String,
+    score: usize,
+    ranks: BinaryHeap<usize>,
+}
+
+impl From<VoteTally> for FinalVoteTally {
+    fn from(value: VoteTally) -> Self {
+        FinalVoteTally::new(
+            value.id,
+            value.html_displayable_text,
+            value.score,
+            value.ranks.into_sorted_vec(),
+        )
+    }
+}
+
+impl TallyMethod for BordaCount {
+    fn tally(
+        &self,
+        ballots: &[Ballot],
+        options: &Options,
+        settings: &RoomSettings,
+    ) -> TallyOutcome {
+        let votes = ranked_ballots(ballots);
+        let option_count = options.values().filter(|o| !o.vetoed).count();
+        let mut tallies: HashMap<Uuid, VoteTally> = HashMap::new();
+        for votes in votes.iter() {
+            for (index, option_id) in votes.iter().enumerate() {
+                let score =
+                    borda_points(settings.partial_ballots, index, votes.len(), option_count);
+                let rank = index + 1;
+                tallies
+                    .entry(*option_id)
+                    .and_modify(|tally| {
+                        tally.score += score;
+                        tally.ranks.push(rank);
+                    })
+                    .or_insert_with(|| VoteTally {
+                        id: *option_id,
+                        html_displayable_text: get_option_html_displayable_text(options, option_id),
+                        score,
+                        ranks: {
+                            let mut heap = BinaryHeap::new();
+                            heap.push(rank);
+                            heap
+                        },
+                    });
+            }
+        }
+
+        if !votes.is_empty() {
+            // Options nobody ranked still finish, with no points
+            for option in options.values().filter(|o| !o.vetoed) {
+                tallies.entry(option.id).or_insert_with(|| VoteTally {
+                    id: option.id,
+                    html_displayable_text: option.get_html_text(),
+                    score: 0,
+                    ranks: BinaryHeap::new(),
+                });
+            }
+        }
+
+        let tallies: Vec<FinalVoteTally> = tallies
+            .into_values()
+            .map(FinalVoteTally::from)
+            .sorted_by_key(|v| v.html_displayable_text.clone())
+            .sorted_by_key(|v| v.score)
+            .rev()
+            .collect();
+
+        let undecided = score_ties(&tallies);
+        TallyOutcome::new(tallies, TallyBreakdown::None, undecided)
+    }
+}
+
+impl TallyMethod for InstantRunoff {
+    fn tally(
+        &self,
+        ballots: &[Ballot],
+        options: &Options,
+        settings: &RoomSettings,
+    ) -> TallyOutcome {
+        let votes = ranked_ballots(ballots);
+        let mut continuing: HashSet<Uuid> = votes.iter().flatten().cloned().collect();
+        let mut rounds = Vec::new();
+        let mut round_counts: Vec<HashMap<Uuid, usize>> = Vec::new();
+        let mut last_counts: HashMap<Uuid, usize> = HashMap::new();
+        let mut elimination_order: Vec<Uuid> = Vec::new();
+
+        while !continuing.is_empty() {
+            let mut counts: HashMap<Uuid, usize> = continuing.iter().map(|id| (*id, 0)).collect();
+            for ballot in votes.iter() {
+                if let Some(top) = first_continuing_choice(ballot, &continuing) {
+                    *counts.get_mut(&top).unwrap() += 1;
+                }
+            }
+            last_counts.extend(counts.iter().map(|(id, count)| (*id, *count)));
+
+            let active_ballots: usize = counts.values().sum();
+            let highest = counts.values().copied().max().unwrap_or(0);
+            let lowest = counts.values().copied().min().unwrap_or(0);
+            let sorted_counts = sort_counts(&counts, options);
+            if highest * 2 > active_ballots || highest == lowest {
+                rounds.push(RunoffRound {
+                    counts: sorted_counts,
+                    eliminated: None,
+                    elimination_tie: None,
+                    transfers: Vec::new(),
+                });
+                break;
+            }
+
+            // Only one option goes per round, since eliminating several at once can change the winner
+            let lowest_options: Vec<Uuid> = sorted_counts
+                .iter()
+                .filter(|(_, count)| *count == lowest)
+                .map(|(id, _)| *id)
+                .collect();
+            let (eliminated, elimination_tie) = match lowest_options.as_slice() {
+                [only] => (*only, None),
+                tied => {
+                    let (eliminated, decided_by) =
+                        pick_elimination(tied, &round_counts, ballots, settings);
+                    let tie = EliminationTie {
+                        tied: tied.to_vec(),
+                        decided_by,
+                    };
+                    (eliminated, Some(tie))
+                }
+            };
+            let remaining: HashSet<Uuid> = continuing
+                .iter()
+                .filter(|id| **id != eliminated)
+                .cloned()
+                .collect();
+
+            let mut transfers: HashMap<Option<Uuid>, usize> = HashMap::new();
+            for ballot in votes.iter() {
+                if first_continuing_choice(ballot, &continuing) == Some(eliminated) {
+                    *transfers
+                        .entry(first_continuing_choice(ballot, &remaining))
+                        .or_default() += 1;
+                }
+            }
+
+            rounds.push(RunoffRound {
+                counts: sorted_counts,
+                eliminated: Some(eliminated),
+                elimination_tie,
+                transfers: transfers
+                    .into_iter()
+                    .sorted_by_key(|(to, count)| (std::cmp::Reverse(*count), to.is_none()))
+                    .collect(),
+            });
+            round_counts.push(counts);
+            elimination_order.push(eliminated);
+            continuing = remaining;
+        }
+
+        let finishing_order = sort_counts(
+            &continuing.iter().map(|id| (*id, last_counts[id])).collect(),
+            options,
+        )
+        .into_iter()
+        .map(|(id, _)| id)
+        .chain(elimination_order.into_iter().rev());
+
+        let tallies: Vec<FinalVoteTally> = finishing_order
+            .map(|id| {
+                FinalVoteTally::new(
+                    id,
+                    get_option_html_displayable_text(options, &id),
+                    last_counts[&id],
+                    collect_ranks(&votes, &id),
+                )
+            })
+            .collect();
+
+        // Eliminated options finish in the order they went, so only the last ones standing can tie
+        let undecided = score_ties(&tallies[..continuing.len()]);
+        TallyOutcome::new(tallies, TallyBreakdown::InstantRunoff(rounds), undecided)
+    }
+}
+
+/// Picks which of the options tied for the fewest votes to eliminate. The one that had the fewest
+/// votes in the latest earlier round where their counts differed goes first. If that doesn't
+/// settle it, the room's tie-break policy does, and failing that the last in option order goes.
+fn pick_elimination(
+    tied: &[Uuid],
+    round_counts: &[HashMap<Uuid, usize>],
+    ballots: &[Ballot],
+    settings: &RoomSettings,
+) -> (Uuid, EliminationTieBreak) {
+    let mut candidates = tied.to_vec();
+    for counts in round_counts.iter().rev() {
+        let fewest = candidates.iter().map(|id| counts[id]).min().unwrap_or(0);
+        candidates.retain(|id| counts[id] == fewest);
+        if candidates.len() == 1 {
+            return (candidates[0], EliminationTieBreak::EarlierRound);
+        }
+    }
+
+    let worst = order_tied(&candidates, ballots, settings)
+        .pop()
+        .unwrap_or_default();
+    match worst.as_slice() {
+        [only] => (*only, EliminationTieBreak::Policy(settings.tie_break)),
+        // `tied` is in option order, and `order_tied` keeps that order within a group
+        _ => (
+            *worst.last().unwrap_or(&tied[tied.len() - 1]),
+            EliminationTieBreak::OptionOrder,
+        ),
+    }
+}
+
+impl TallyMethod for Schulze {
+    fn tally(
+        &self,
+        ballots: &[Ballot],
+        options: &Options,
+        settings: &RoomSettings,
+    ) -> TallyOutcome {
+        let votes = ranked_ballots(ballots);
+        let mut candidates: Vec<Uuid> = votes.iter().flatten().cloned().unique().collect();
+        if !votes.is_empty() {
+            // Options nobody ranked still finish, behind anything that beats them
+            let unranked: Vec<Uuid> = options
+                .values()
+                .filter(|o| !o.vetoed && !candidates.contains(&o.id))
+                .map(|o| o.id)
+                .collect();
+            candidates.extend(unranked);
+        }
+        let preferences = pairwise_preferences(&votes, &candidates, settings.partial_ballots);
+        let n = candidates.len();
+
+        let mut strongest_paths = vec![vec![0; n]; n];
+        for i in 0..n {
+            for j in 0..n {
+                if i != j && preferences[i][j] > preferences[j][i] {
+                    strongest_paths[i][j] = preferences[i][j];
+                }
+            }
+        }
+        for k in 0..n {
+            for i in 0..n {
+                for j in 0..n {
+                    if i != j && i != k && j != k {
+                        strongest_paths[i][j] = strongest_paths[i][j]
+                            .max(strongest_paths[i][k].min(strongest_paths[k][j]));
+                    }
+                }
+            }
+        }
+
+        let wins: Vec<usize> = (0..n)
+            .map(|i| {
+                (0..n)
+                    .filter(|&j| strongest_paths[i][j] > strongest_paths[j][i])
+                    .count()
+            })
+            .collect();
+        let condorcet_winner = (0..n)
+            .find(|&i| (0..n).all(|j| i == j || preferences[i][j] > preferences[j][i]))
+            .map(|i| candidates[i]);
+
+        let order: Vec<usize> = (0..n)
+            .sorted_by_key(|&i| get_option_html_displayable_text(options, &candidates[i]))
+            .sorted_by_key(|&i| std::cmp::Reverse(wins[i]))
+            .collect();
+        let tallies: Vec<FinalVoteTally> = order
+            .iter()
+            .map(|&i| {
+                FinalVoteTally::new(
+                    candidates[i],
+                    get_option_html_displayable_text(options, &candidates[i]),
+                    wins[i],
+                    collect_ranks(&votes, &candidates[i]),
+                )
+            })
+            .collect();
+
+        let undecided = score_ties(&tallies);
+        TallyOutcome::new(
+            tallies,
+            TallyBreakdown::Pairwise(PairwiseMatrix {
+                options: order.iter().map(|&i| candidates[i]).collect(),
+                preferences: order
+                    .iter()
+                    .map(|&i| order.iter().map(|&j| preferences[i][j]).collect())
+                    .collect(),
+                condorcet_winner,
+            }),
+            undecided,
+        )
+    }
+}
+
+impl TallyMethod for ApprovalCount {
+    fn tally(
+        &self,
+        ballots: &[Ballot],
+        options: &Options,
+        _settings: &RoomSettings,
+    ) -> TallyOutcome {
+        let mut approvals: HashMap<Uuid, usize> = HashMap::new();
+        for ballot in ballots.iter() {
+            if let Ballot::Approval(ids) = ballot {
+                for id in ids.iter() {
+                    *approvals.entry(*id).or_default() += 1;
+                }
+            }
+        }
+
+        if ballots
+            .iter()
+            .any(|ballot| matches!(ballot, Ballot::Approval(_)))
+        {
+            // Options nobody approved of still finish, with no approvals
+            for option in options.values().filter(|o| !o.vetoed) {
+                approvals.entry(option.id).or_default();
+            }
+        }
+
+        let tallies: Vec<FinalVoteTally> = sort_counts(&approvals, options)
+            .into_iter()
+            .map(|(id, count)| {
+                FinalVoteTally::new(
+                    id,
+                    get_option_html_displayable_text(options, &id),
+                    count,
+                    Vec::new(),
+                )
+            })
+            .collect();
+
+        let undecided = score_ties(&tallies);
+        TallyOutcome::new(tallies, TallyBreakdown::None, undecided)
+    }
+}
+
+impl TallyMethod for Star {
+    fn tally(
+        &self,
+        ballots: &[Ballot],
+        options: &Options,
+        _settings: &RoomSettings,
+    ) -> TallyOutcome {
+        let score_ballots: Vec<HashMap<Uuid, u8>> = ballots
+            .iter()
+            .filter_map(|ballot| match ballot {
+                Ballot::Score(scores) => Some(scores.iter().cloned().collect()),
+                _ => None,
+            })
+            .collect();
+
+        let mut totals: HashMap<Uuid, usize> = HashMap::new();
+        for scores in score_ballots.iter() {
+            for (id, score) in scores.iter() {
+                *totals.entry(*id).or_default() += usize::from((*score).min(MAX_SCORE));
+            }
+        }
+        if !score_ballots.is_empty() {
+            // Options nobody scored still finish, with no points
+            for option in options.values().filter(|o| !o.vetoed) {
+                totals.entry(option.id).or_default();
+            }
+        }
+
+        let mut order = sort_counts(&totals, options);
+        let mut breakdown = TallyBreakdown::None;
+        let mut runoff_decided = false;
+        if order.len() >= 2 {
+            let (first, second) = (order[0].0, order[1].0);
+            let mut preferred_counts = (0, 0);
+            let mut no_preference = 0;
+            for scores in score_ballots.iter() {
+                let first_score = scores.get(&first).copied().unwrap_or(0);
+                let second_score = scores.get(&second).copied().unwrap_or(0);
+                match first_score.cmp(&second_score) {
+                    std::cmp::Ordering::Greater => preferred_counts.0 += 1,
+                    std::cmp::Ordering::Less => preferred_counts.1 += 1,
+                    std::cmp::Ordering::Equal => no_preference += 1,
+                }
+            }
+
+            if preferred_counts.1 > preferred_counts.0 {
+                order.swap(0, 1);
+            }
+            runoff_decided = preferred_counts.0 != preferred_counts.1;
+            breakdown = TallyBreakdown::StarRunoff(StarRunoff {
+                finalists: (first, second),
+                preferred_counts,
+                no_preference,
+            });
+        }
+
+        let tallies: Vec<FinalVoteTally> = order
+            .into_iter()
+            .map(|(id, total)| {
+                FinalVoteTally::new(
+                    id,
+                    get_option_html_displayable_text(options, &id),
+                    total,
+                    Vec::new(),
+                )
+            })
+            .collect();
+
+        // The runoff settles first place even between finalists with the same total
+        let undecided = if runoff_decided {
+            score_ties(&tallies[1..])
+        } else {
+            score_ties(&tallies)
+        };
+        TallyOutcome::new(tallies, breakdown, undecided)
+    }
+}
+
+/// Groups of neighbouring tallies with the same score, for methods where the score is all there is
+/// to go on
+fn score_ties(tallies: &[FinalVoteTally]) -> Vec<Vec<Uuid>> {
+    tallies
+        .iter()
+        .group_by(|tally| tally.score)
+        .into_iter()
+        .map(|(_, group)| group.map(|tally| tally.id).collect::<Vec<_>>())
+        .filter(|group| group.len() > 1)
+        .collect()
+}
+
+fn ranked_ballots(ballots: &[Ballot]) -> Vec<Vec<Uuid>> {
+    ballots
+        .iter()
+        .filter_map(|ballot| match ballot {
+            Ballot::Ranked(ids) => Some(ids.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Points a ranked ballot gives the option at `index`, out of `option_count` options that could be ranked
+fn borda_points(
+    policy: PartialBallotPolicy,
+    index: usize,
+    ballot_len: usize,
+    option_count: usize,
+) -> usize {
+    match policy {
+        PartialBallotPolicy::UnrankedTieForLast => option_count.saturating_sub(index),
+        PartialBallotPolicy::IgnoreUnranked => ballot_len - index,
+        PartialBallotPolicy::Normalize => {
+            ((ballot_len - index) * option_count + ballot_len / 2) / ballot_len
+        }
+    }
+}
+
+/// Counts, for every ordered pair of candidates, how many ballots rank the first above the second.
+/// Unless `policy` ignores unranked options, a ranked option is preferred over any option the ballot leaves unranked.
+pub fn pairwise_preferences(
+    votes: &[Vec<Uuid>],
+    candidates: &[Uuid],
+    policy: PartialBallotPolicy,
+) -> Vec<Vec<usize>> {
+    let n = candidates.len();
+    let mut preferences = vec![vec![0; n]; n];
+    for ballot in votes.iter() {
+        let positions: Vec<Option<usize>> = candidates
+            .iter()
+            .map(|id| ballot.iter().position(|v| v == id))
+            .collect();
+        for i in 0..n {
+            for j in 0..n {
+                let prefers_i = match (positions[i], positions[j]) {
+                    (Some(pi), Some(pj)) => pi < pj,
+                    (Some(_), None) => policy != PartialBallotPolicy::IgnoreUnranked,
+                    _ => false,
+                };
+                if prefers_i {
+                    preferences[i][j] += 1;
+                }
+            }
+        }
+    }
+
+    preferences
+}
+
+fn first_continuing_choice(ballot: &[Uuid], continuing: &HashSet<Uuid>) -> Option<Uuid> {
+    ballot.iter().find(|id| continuing.contains(id)).cloned()
+}
+
+/// Sorts by count, highest first, falling back to the option text so the order is stable
+fn sort_counts(counts: &HashMap<Uuid, usize>, options: &Options) -> Vec<(Uuid, usize)> {
+    counts
+        .iter()
+        .map(|(id, count)| (*id, *count))
+        .sorted_by_key(|(id, _)| get_option_html_displayable_text(options, id))
+        .sorted_by_key(|(_, count)| std::cmp::Reverse(*count))
+        .collect()
+}
+
+fn collect_ranks(votes: &[Vec<Uuid>], id: &Uuid) -> Vec<usize> {
+    votes
+        .iter()
+        .filter_map(|ballot| ballot.iter().position(|v| v == id))
+        .map(|index| index + 1)
+        .sorted()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::room_state::test_support::options;
+
+    fn ranked(count: usize, ranking: &[Uuid]) -> Vec<Ballot> {
+        vec![Ballot::Ranked(ranking.to_vec()); count]
+    }
+
+    fn finishing_order(outcome: &TallyOutcome) -> Vec<Uuid> {
+        outcome.tallies.iter().map(|t| t.id).collect()
+    }
+
+    fn runoff_rounds(outcome: &TallyOutcome) -> &[RunoffRound] {
+        match &outcome.breakdown {
+            TallyBreakdown::InstantRunoff(rounds) => rounds,
+            _ => panic!("Expected an instant-runoff breakdown"),
+        }
+    }
+
+    #[test]
+    fn instant_runoff_eliminates_one_option_per_round() {
+        let (options, ids) = options(&["A", "B", "C"]);
+        let [a, b, c] = [ids[0], ids[1], ids[2]];
+        let ballots = [ranked(4, &[a, c]), ranked(3, &[b, c]), ranked(3, &[c, a])].concat();
+        let settings = RoomSettings {
+            tie_break: TieBreakPolicy::HeadToHead,
+            ..RoomSettings::default()
+        };
+
+        let outcome = InstantRunoff.tally(&ballots, &options, &settings);
+
+        // Knocking out B and C together would hand A the win, but B's voters would rather have C
+        assert_eq!(finishing_order(&outcome), vec![c, a, b]);
+        assert_eq!(outcome.tallies[0].score, 6);
+        let rounds = runoff_rounds(&outcome);
+        assert_eq!(rounds.len(), 2);
+        assert_eq!(rounds[0].eliminated, Some(b));
+        let tie = rounds[0].elimination_tie.as_ref().unwrap();
+        assert_eq!(tie.tied, vec![b, c]);
+        assert_eq!(
+            tie.decided_by,
+            EliminationTieBreak::Policy(TieBreakPolicy::HeadToHead)
+        );
+        assert_eq!(rounds[1].eliminated, None);
+    }
+
+    #[test]
+    fn instant_runoff_breaks_elimination_ties_by_earlier_rounds() {
+        let (options, ids) = options(&["A", "B", "C", "D"]);
+        let [a, b, c, d] = [ids[0], ids[1], ids[2], ids[3]];
+        let ballots = [
+            ranked(6, &[a]),
+            ranked(3, &[b]),
+            ranked(2, &[c]),
+            ranked(1, &[d, c]),
+        ]
+        .concat();
+
+        let outcome = InstantRunoff.tally(&ballots, &options, &RoomSettings::default());
+
+        let rounds = runoff_rounds(&outcome);
+        assert_eq!(rounds[0].eliminated, Some(d));
+        assert!(rounds[0].elimination_tie.is_none());
+        // B and C both have 3 votes once D's vote moves, but C had fewer to start with
+        assert_eq!(rounds[1].eliminated, Some(c));
+        assert_eq!(
+            rounds[1].elimination_tie.as_ref().unwrap().decided_by,
+            EliminationTieBreak::EarlierRound
+        );
+        assert_eq!(finishing_order(&outcome), vec![a, b, c, d]);
+    }
+
+    #[test]
+    fn instant_runoff_falls_back_to_option_order() {
+        let (options, ids) = options(&["A", "B", "C"]);
+        let [a, b, c] = [ids[0], ids[1], ids[2]];
+        let ballots = [ranked(4, &[a]), ranked(3, &[b]), ranked(3, &[c])].concat();
+        let settings = RoomSettings {
+            tie_break: TieBreakPolicy::DeclareTie,
+            ..RoomSettings::default()
+        };
+
+        let outcome = InstantRunoff.tally(&ballots, &options, &settings);
+
+        let rounds = runoff_rounds(&outcome);
+        assert_eq!(rounds[0].eliminated, Some(c));
+        assert_eq!(
+            rounds[0].elimination_tie.as_ref().unwrap().decided_by,
+            EliminationTieBreak::OptionOrder
+        );
+    }
+
+    #[test]
+    fn schulze_follows_the_strongest_paths_through_a_cycle() {
+        let (options, ids) = options(&["A", "B", "C"]);
+        let [a, b, c] = [ids[0], ids[1], ids[2]];
+        // A beats B, B beats C and C beats A, but C's win over A is the narrowest
+        let ballots = [
+            ranked(3, &[a, b, c]),
+            ranked(2, &[b, c, a]),
+            ranked(2, &[c, a, b]),
+        ]
+        .concat();
+
+        let outcome = Schulze.tally(&ballots, &options, &RoomSettings::default());
+
+        assert_eq!(finishing_order(&outcome), vec![a, b, c]);
+        assert!(outcome.undecided.is_empty());
+        match &outcome.breakdown {
+            TallyBreakdown::Pairwise(matrix) => assert_eq!(matrix.condorcet_winner, None),
+            _ => panic!("Expected a pairwise breakdown"),
+        }
+    }
+
+    #[test]
+    fn star_leaves_finalists_undecided_only_when_the_runoff_ties() {
+        let (options, ids) = options(&["A", "B"]);
+        let [a, b] = [ids[0], ids[1]];
+        let ballots = vec![
+            Ballot::Score(vec![(a, 5), (b, 0)]),
+            Ballot::Score(vec![(a, 0), (b, 5)]),
+        ];
+
+        let outcome = Star.tally(&ballots, &options, &RoomSettings::default());
+
+        assert_eq!(outcome.undecided, vec![vec![a, b]]);
+    }
+
+    #[test]
+    fn approval_includes_options_nobody_approved() {
+        let (options, ids) = options(&["A", "B", "C"]);
+        let ballots = vec![Ballot::Approval(vec![ids[1]]); 2];
+
+        let outcome = ApprovalCount.tally(&ballots, &options, &RoomSettings::default());
+
+        assert_eq!(outcome.tallies.len(), 3);
+        assert_eq!(outcome.tallies[0].id, ids[1]);
+        assert_eq!(outcome.tallies[0].score, 2);
+        assert!(outcome.tallies[1..].iter().all(|t| t.score == 0));
+    }
+
+    #[test]
+    fn star_includes_options_nobody_scored() {
+        let (options, ids) = options(&["A", "B", "C"]);
+        let ballots = vec![Ballot::Score(vec![(ids[0], 3), (ids[1], 5)]); 2];
+
+        let outcome = Star.tally(&ballots, &options, &RoomSettings::default());
+
+        assert_eq!(finishing_order(&outcome), [ids[1], ids[0], ids[2]]);
+        assert_eq!(outcome.tallies[2].score, 0);
+    }
+
+    #[test]
+    fn schulze_includes_options_nobody_ranked() {
+        let (options, ids) = options(&["A", "B", "C"]);
+        let ballots = ranked(2, &[ids[1], ids[0]]);
+
+        let outcome = Schulze.tally(&ballots, &options, &RoomSettings::default());
+
+        assert_eq!(finishing_order(&outcome)[..2], [ids[1], ids[0]]);
+        assert_eq!(outcome.tallies[2].id, ids[2]);
+    }
+}
@@ -0,0 +1,54 @@
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use crate::storage::writer::StorageWriter;
+
+use super::{
+    import::ImportedOption,
+    option_details::OptionDetails,
+    participant::{Participant, ParticipantId},
+    Option, Options, RoomSettings, RoomState,
+};
+
+/// Options with the given texts, in that order, along with their ids
+pub(crate) fn options(texts: &[&str]) -> (Options, Vec<Uuid>) {
+    let added_by = ParticipantId::new();
+    let mut options = Options::new();
+    let mut ids = Vec::new();
+    for text in texts {
+        let option = Option::new(text.to_string(), OptionDetails::default(), added_by);
+        ids.push(option.id);
+        options.insert(option.id, option);
+    }
+    (options, ids)
+}
+
+/// A room with options with the given texts that saves nothing, along with its host and the options' ids
+pub(crate) fn room(
+    texts: &[&str],
+    settings: RoomSettings,
+) -> (RoomState, ParticipantId, Vec<Uuid>) {
+    let host = ParticipantId::new();
+    let options = texts
+        .iter()
+        .map(|text| ImportedOption::new(text.to_string(), OptionDetails::default()))
+        .collect();
+    let (broadcast_tx, _) = broadcast::channel(16);
+    let room = RoomState::new(
+        "test".to_string(),
+        options,
+        settings,
+        Participant::new(host, "Host").unwrap(),
+        broadcast_tx,
+        StorageWriter::disabled(),
+    );
+    let ids = room.iter_options().map(|o| o.id).collect();
+    (room, host, ids)
+}
+
+/// Has someone new join the room, returning their id
+pub(crate) fn join(room: &mut RoomState, nickname: &str) -> ParticipantId {
+    let id = ParticipantId::new();
+    room.join(id, nickname).unwrap();
+    id
+}
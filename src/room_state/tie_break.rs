@@ -0,0 +1,300 @@
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::{
+    ballot::{Ballot, PartialBallotPolicy},
+    tally::TallyOutcome,
+    FinalVoteTally, RoomSettings,
+};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TieBreakPolicy {
+    #[default]
+    MostFirstPlace,
+    HeadToHead,
+    SeededRandom,
+    DeclareTie,
+}
+
+/// A group of options that finished with the same score, and how the tie between them was handled
+#[derive(Serialize)]
+pub struct TieBreak {
+    pub tied: Vec<Uuid>,
+    pub policy: TieBreakPolicy,
+    /// Whether the policy managed to fully separate the tied options
+    pub resolved: bool,
+}
+
+impl TieBreakPolicy {
+    pub const ALL: [TieBreakPolicy; 4] = [
+        TieBreakPolicy::MostFirstPlace,
+        TieBreakPolicy::HeadToHead,
+        TieBreakPolicy::SeededRandom,
+        TieBreakPolicy::DeclareTie,
+    ];
+
+    pub fn form_value(&self) -> &'static str {
+        match self {
+            TieBreakPolicy::MostFirstPlace => "MostFirstPlace",
+            TieBreakPolicy::HeadToHead => "HeadToHead",
+            TieBreakPolicy::SeededRandom => "SeededRandom",
+            TieBreakPolicy::DeclareTie => "DeclareTie",
+        }
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            TieBreakPolicy::MostFirstPlace => "Most first-place votes",
+            TieBreakPolicy::HeadToHead => "Head-to-head result",
+            TieBreakPolicy::SeededRandom => "Seeded random draw",
+            TieBreakPolicy::DeclareTie => "Declare a tie",
+        }
+    }
+}
+
+/// Orders each group of options the tally method left undecided according to the room's tie-break
+/// policy, then assigns every tally its finishing place. Options the policy couldn't separate share a
+/// place.
+pub fn break_ties(outcome: &mut TallyOutcome, ballots: &[Ballot], settings: &RoomSettings) {
+    let mut tallies = std::mem::take(&mut outcome.tallies).into_iter().peekable();
+    let mut ordered: Vec<FinalVoteTally> = Vec::with_capacity(tallies.len());
+    while let Some(tally) = tallies.next() {
+        let Some(tied) = outcome
+            .undecided
+            .iter()
+            .find(|group| group.contains(&tally.id))
+        else {
+            let place = ordered.len() + 1;
+            ordered.push(with_place(tally, place));
+            continue;
+        };
+
+        let mut group = vec![Some(tally)];
+        while let Some(tally) = tallies.next_if(|t| tied.contains(&t.id)) {
+            group.push(Some(tally));
+        }
+
+        let subgroups = order_tied(tied, ballots, settings);
+        outcome.tie_breaks.push(TieBreak {
+            tied: tied.clone(),
+            policy: settings.tie_break,
+            resolved: subgroups.iter().all(|s| s.len() == 1),
+        });
+
+        for subgroup in subgroups {
+            let place = ordered.len() + 1;
+            for id in subgroup {
+                let tally = group
+                    .iter_mut()
+                    .find(|t| t.as_ref().is_some_and(|t| t.id == id))
+                    .and_then(|t| t.take())
+                    .unwrap();
+                ordered.push(with_place(tally, place));
+            }
+        }
+    }
+
+    outcome.tallies = ordered;
+}
+
+fn with_place(mut tally: FinalVoteTally, place: usize) -> FinalVoteTally {
+    tally.place = place;
+    tally
+}
+
+/// Splits the tied options into groups from best to worst. Options in the same group are still tied.
+pub(crate) fn order_tied(
+    tied: &[Uuid],
+    ballots: &[Ballot],
+    settings: &RoomSettings,
+) -> Vec<Vec<Uuid>> {
+    match settings.tie_break {
+        TieBreakPolicy::MostFirstPlace => group_by_descending_key(tied, |id| {
+            ballots.iter().filter(|b| b.is_top_choice(id)).count() as u64
+        }),
+        TieBreakPolicy::HeadToHead => group_by_descending_key(tied, |id| {
+            tied.iter()
+                .filter(|other| {
+                    *other != id && beats_head_to_head(id, other, ballots, settings.partial_ballots)
+                })
+                .count() as u64
+        }),
+        TieBreakPolicy::SeededRandom => tied
+            .iter()
+            .sorted_by_key(|id| seeded_draw(settings.tie_break_seed, id))
+            .map(|id| vec![*id])
+            .collect(),
+        TieBreakPolicy::DeclareTie => vec![tied.to_vec()],
+    }
+}
+
+fn group_by_descending_key(tied: &[Uuid], key: impl Fn(&Uuid) -> u64) -> Vec<Vec<Uuid>> {
+    tied.iter()
+        .map(|id| (key(id), *id))
+        .sorted_by_key(|(key, _)| std::cmp::Reverse(*key))
+        .group_by(|(key, _)| *key)
+        .into_iter()
+        .map(|(_, group)| group.map(|(_, id)| id).collect())
+        .collect()
+}
+
+fn beats_head_to_head(
+    a: &Uuid,
+    b: &Uuid,
+    ballots: &[Ballot],
+    partial_ballots: PartialBallotPolicy,
+) -> bool {
+    let prefer_a = ballots
+        .iter()
+        .filter(|ballot| ballot.prefers(a, b, partial_ballots))
+        .count();
+    let prefer_b = ballots
+        .iter()
+        .filter(|ballot| ballot.prefers(b, a, partial_ballots))
+        .count();
+    prefer_a > prefer_b
+}
+
+/// A deterministic pseudo-random draw for an option, so anyone with the seed can reproduce the result
+fn seeded_draw(seed: u64, id: &Uuid) -> u64 {
+    let (high, low) = id.as_u64_pair();
+    splitmix64(seed ^ splitmix64(high ^ splitmix64(low)))
+}
+
+fn splitmix64(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9E3779B97F4A7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::room_state::{
+        tally::{ApprovalCount, BordaCount, InstantRunoff, Star, TallyMethod},
+        test_support::options,
+    };
+
+    fn settings(tie_break: TieBreakPolicy) -> RoomSettings {
+        RoomSettings {
+            tie_break,
+            ..RoomSettings::default()
+        }
+    }
+
+    fn places(outcome: &TallyOutcome) -> Vec<(Uuid, usize)> {
+        outcome.tallies.iter().map(|t| (t.id, t.place)).collect()
+    }
+
+    #[test]
+    fn star_runoff_winner_keeps_first_place_on_equal_totals() {
+        let (options, ids) = options(&["A", "B"]);
+        let [a, b] = [ids[0], ids[1]];
+        // Both total 6, but two of the three ballots score B higher
+        let ballots = vec![
+            Ballot::Score(vec![(a, 4), (b, 0)]),
+            Ballot::Score(vec![(a, 1), (b, 3)]),
+            Ballot::Score(vec![(a, 1), (b, 3)]),
+        ];
+        let settings = settings(TieBreakPolicy::MostFirstPlace);
+
+        let mut outcome = Star.tally(&ballots, &options, &settings);
+        break_ties(&mut outcome, &ballots, &settings);
+
+        assert_eq!(places(&outcome), vec![(b, 1), (a, 2)]);
+        assert!(outcome.tie_breaks.is_empty());
+    }
+
+    #[test]
+    fn instant_runoff_finishing_order_is_left_alone() {
+        let (options, ids) = options(&["A", "B", "C", "D"]);
+        let [a, b, c, d] = [ids[0], ids[1], ids[2], ids[3]];
+        // B and C both end on 3 votes, but C was eliminated first
+        let ballots = [
+            vec![Ballot::Ranked(vec![a]); 5],
+            vec![Ballot::Ranked(vec![b]); 3],
+            vec![Ballot::Ranked(vec![c]); 2],
+            vec![Ballot::Ranked(vec![d, c])],
+        ]
+        .concat();
+        let settings = settings(TieBreakPolicy::SeededRandom);
+
+        let mut outcome = InstantRunoff.tally(&ballots, &options, &settings);
+        break_ties(&mut outcome, &ballots, &settings);
+
+        assert_eq!(places(&outcome), vec![(a, 1), (b, 2), (c, 3), (d, 4)]);
+        assert!(outcome.tie_breaks.is_empty());
+    }
+
+    #[test]
+    fn most_first_place_breaks_score_ties() {
+        let (options, ids) = options(&["A", "B", "C"]);
+        let [a, b, c] = [ids[0], ids[1], ids[2]];
+        // A and B both get 5 Borda points, but only B is anyone's favourite
+        let ballots = vec![
+            Ballot::Ranked(vec![b, c, a]),
+            Ballot::Ranked(vec![c, a, b]),
+            Ballot::Ranked(vec![c, a, b]),
+        ];
+        let settings = settings(TieBreakPolicy::MostFirstPlace);
+
+        let mut outcome = BordaCount.tally(&ballots, &options, &settings);
+        break_ties(&mut outcome, &ballots, &settings);
+
+        assert_eq!(places(&outcome), vec![(c, 1), (b, 2), (a, 3)]);
+        assert_eq!(outcome.tie_breaks.len(), 1);
+        assert!(outcome.tie_breaks[0].resolved);
+    }
+
+    #[test]
+    fn head_to_head_breaks_score_ties() {
+        let (options, ids) = options(&["A", "B"]);
+        let [a, b] = [ids[0], ids[1]];
+        let ballots = vec![
+            Ballot::Approval(vec![a]),
+            Ballot::Approval(vec![b]),
+            Ballot::Ranked(vec![b, a]),
+        ];
+        let settings = settings(TieBreakPolicy::HeadToHead);
+
+        let mut outcome = ApprovalCount.tally(&ballots, &options, &settings);
+        break_ties(&mut outcome, &ballots, &settings);
+
+        assert_eq!(places(&outcome), vec![(b, 1), (a, 2)]);
+    }
+
+    #[test]
+    fn declared_ties_share_a_place() {
+        let (options, ids) = options(&["A", "B", "C"]);
+        let [a, b, c] = [ids[0], ids[1], ids[2]];
+        let ballots = vec![Ballot::Ranked(vec![a, b, c]), Ballot::Ranked(vec![b, a, c])];
+        let settings = settings(TieBreakPolicy::DeclareTie);
+
+        let mut outcome = BordaCount.tally(&ballots, &options, &settings);
+        break_ties(&mut outcome, &ballots, &settings);
+
+        let place = |id| outcome.tallies.iter().find(|t| t.id == id).unwrap().place;
+        assert_eq!([place(a), place(b), place(c)], [1, 1, 3]);
+        assert!(!outcome.tie_breaks[0].resolved);
+    }
+
+    #[test]
+    fn seeded_draws_are_reproducible() {
+        let (options, ids) = options(&["A", "B", "C", "D"]);
+        let ballots = vec![Ballot::Approval(ids.clone())];
+        let settings = settings(TieBreakPolicy::SeededRandom);
+
+        let mut first = ApprovalCount.tally(&ballots, &options, &settings);
+        let mut second = ApprovalCount.tally(&ballots, &options, &settings);
+        break_ties(&mut first, &ballots, &settings);
+        break_ties(&mut second, &ballots, &settings);
+
+        assert_eq!(places(&first), places(&second));
+        assert_eq!(
+            first.tallies.iter().map(|t| t.place).collect::<Vec<_>>(),
+            vec![1, 2, 3, 4]
+        );
+    }
+}
@@ -0,0 +1,55 @@
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_READY_QUORUM_PERCENT: u8 = 100;
+
+/// How a room decides that the veto phase is over
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum VetoCompletion {
+    /// Everyone marks themselves done, and the room moves on once enough of them have
+    #[default]
+    ReadyCheck,
+    /// The first person to finish vetoing moves everyone on
+    Immediate,
+}
+
+impl VetoCompletion {
+    pub const ALL: [VetoCompletion; 2] = [VetoCompletion::ReadyCheck, VetoCompletion::Immediate];
+
+    pub fn form_value(&self) -> &'static str {
+        match self {
+            VetoCompletion::ReadyCheck => "ReadyCheck",
+            VetoCompletion::Immediate => "Immediate",
+        }
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            VetoCompletion::ReadyCheck => "Ready check",
+            VetoCompletion::Immediate => "First to finish",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            VetoCompletion::ReadyCheck => "Each voter marks themselves done vetoing. Everyone moves on to voting together once enough of the people in the room are done, or when the host decides to move on.",
+            VetoCompletion::Immediate => "As soon as any voter finishes vetoing, everyone moves on to voting together and can no longer veto.",
+        }
+    }
+}
+
+pub fn default_ready_quorum_percent() -> u8 {
+    DEFAULT_READY_QUORUM_PERCENT
+}
+
+/// Rooms saved before veto completion was a setting moved on as soon as anyone finished vetoing, so
+/// they keep doing that. New rooms default to `VetoCompletion::ReadyCheck`.
+pub fn default_saved_veto_completion() -> VetoCompletion {
+    VetoCompletion::Immediate
+}
+
+/// How many of `eligible` people have to be ready for a quorum of `quorum_percent`. At least one
+/// person always has to be ready.
+pub fn required_ready_count(quorum_percent: u8, eligible: usize) -> usize {
+    let quorum_percent = usize::from(quorum_percent.clamp(1, 100));
+    (eligible * quorum_percent).div_ceil(100).max(1)
+}
@@ -0,0 +1,67 @@
+use anyhow::bail;
+use serde::{Deserialize, Serialize};
+
+use super::participant::ParticipantId;
+
+pub const DEFAULT_VETO_THRESHOLD: u8 = 1;
+pub const MAX_VETO_REASON_LENGTH: usize = 80;
+
+/// One participant's veto of an option
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Veto {
+    pub by: ParticipantId,
+    reason: Option<String>,
+}
+
+impl Veto {
+    /// A blank reason is the same as giving none
+    pub fn new(by: ParticipantId, reason: &str) -> anyhow::Result<Self> {
+        Ok(Self {
+            by,
+            reason: validate_reason(reason)?,
+        })
+    }
+
+    pub fn reason(&self) -> Option<&str> {
+        self.reason.as_deref()
+    }
+
+    pub fn get_html_reason(&self) -> Option<String> {
+        self.reason
+            .as_ref()
+            .map(|reason| ammonia::clean_text(reason))
+    }
+}
+
+pub fn default_veto_threshold() -> u8 {
+    DEFAULT_VETO_THRESHOLD
+}
+
+/// Describes a room's veto rules for the people in it
+pub fn describe_veto_rules(veto_limit: Option<u8>, veto_threshold: u8) -> String {
+    let limit = match veto_limit {
+        Some(1) => "Each voter gets 1 veto.".to_string(),
+        Some(limit) => format!("Each voter gets {} vetoes.", limit),
+        None => "Each voter can veto any number of options.".to_string(),
+    };
+    let threshold = match veto_threshold {
+        0 | 1 => "One veto is enough to strike an option.".to_string(),
+        threshold => format!(
+            "An option is only struck once {} voters have vetoed it.",
+            threshold
+        ),
+    };
+    format!("{} {}", limit, threshold)
+}
+
+fn validate_reason(reason: &str) -> anyhow::Result<Option<String>> {
+    let reason = reason.trim();
+    if reason.chars().count() > MAX_VETO_REASON_LENGTH {
+        bail!(
+            "Veto reasons can be at most {} characters long",
+            MAX_VETO_REASON_LENGTH
+        );
+    }
+
+    Ok((!reason.is_empty()).then(|| reason.to_string()))
+}
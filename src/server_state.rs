@@ -1,44 +1,91 @@
-use tokio::sync::broadcast;
+use anyhow::bail;
+use tokio::{sync::broadcast, task::JoinHandle};
+use tracing::info;
 
 use crate::{
-    pages::{ranking_page::RankingPage, results_page::ResultsPage, veto_page::VetoPage, AppPage},
-    room_state::{RoomState, VotingStage},
-    BroadcastReceiver, BroadcastSender,
+    pages::{get_room_entry_page, results_page::ResultsPage, AppPage},
+    room_state::{
+        import::ImportedOption,
+        participant::{Participant, ParticipantId},
+        RoomSettings, RoomState,
+    },
+    session::SharedSessions,
+    storage::{writer::StorageWriter, RoomStorage},
+    BroadcastReceiver, BroadcastSender, RoomEvent, ServerwideSharedState,
 };
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     sync::{Arc, RwLock},
+    time::Duration,
 };
 
 const ROOM_CODE_CHARSET: &str = "abcdefghijklmnopqrstuvwxyz";
+/// How many events a room's listeners can fall behind by before they have to resync
+const BROADCAST_CHANNEL_CAPACITY: usize = 64;
+const ROOM_REAPER_INTERVAL: Duration = Duration::from_secs(60);
+
+/// A room that was just created, along with its code and the channel its events go out on
+pub type NewRoom<'a> = (
+    String,
+    &'a mut Arc<RwLock<RoomState>>,
+    BroadcastSender,
+    BroadcastReceiver,
+);
 
-#[derive(Default)]
 pub struct ServerState {
     pub rooms: HashMap<String, Arc<RwLock<RoomState>>>,
+    storage: StorageWriter,
+}
+
+impl Default for ServerState {
+    fn default() -> Self {
+        Self {
+            rooms: HashMap::new(),
+            storage: StorageWriter::disabled(),
+        }
+    }
 }
 
 impl ServerState {
+    /// Creates the server state with every room previously saved to `storage`, saving changes
+    /// through `writer` from then on
+    pub fn load(storage: &dyn RoomStorage, writer: StorageWriter) -> anyhow::Result<Self> {
+        let rooms = storage
+            .load_rooms()?
+            .into_iter()
+            .map(|snapshot| {
+                let (broadcast_tx, _) = broadcast::channel(BROADCAST_CHANNEL_CAPACITY);
+                let code = snapshot.code.clone();
+                let room = RoomState::from_snapshot(snapshot, broadcast_tx, writer.clone());
+                (code, Arc::new(RwLock::new(room)))
+            })
+            .collect::<HashMap<_, _>>();
+        info!("Loaded {} saved rooms", rooms.len());
+
+        Ok(Self {
+            rooms,
+            storage: writer,
+        })
+    }
+
     pub fn create_room(
         &mut self,
-        original_input_text: String,
-    ) -> Result<
-        (
-            String,
-            &mut Arc<RwLock<RoomState>>,
-            BroadcastSender,
-            BroadcastReceiver,
-        ),
-        (),
-    > {
+        options: Vec<ImportedOption>,
+        settings: RoomSettings,
+        creator: Participant,
+    ) -> anyhow::Result<NewRoom<'_>> {
         let room_code = self.get_valid_room_code()?;
-        let (broadcast_tx, broadcast_rx) = broadcast::channel(10);
+        let (broadcast_tx, broadcast_rx) = broadcast::channel(BROADCAST_CHANNEL_CAPACITY);
         Ok((
             room_code.clone(),
             self.rooms.entry(room_code.clone()).or_insert_with(|| {
                 Arc::new(RwLock::new(RoomState::new(
                     room_code,
-                    original_input_text,
+                    options,
+                    settings,
+                    creator,
                     broadcast_tx.clone(),
+                    self.storage.clone(),
                 )))
             }),
             broadcast_tx.clone(),
@@ -46,34 +93,28 @@ impl ServerState {
         ))
     }
 
+    /// Finds a room by its code, which people may type in any case
+    pub fn get_room(&self, room_code: &str) -> Option<&Arc<RwLock<RoomState>>> {
+        self.rooms.get(&room_code.to_ascii_lowercase())
+    }
+
+    /// Gets the page for the room's current stage, or asks for a nickname first if the participant hasn't joined
     pub fn get_room_voting_page(
         &self,
         room_code: &str,
+        participant: ParticipantId,
     ) -> Result<(Box<dyn AppPage + Send + Sync>, BroadcastReceiver), String> {
-        if let Some(room) = self.rooms.get(room_code) {
-            let (voting_stage, broadcast_tx) = {
-                let room = room.read().unwrap();
-                (room.voting_stage(), room.get_broadcast_tx())
+        if let Some(room) = self.get_room(room_code) {
+            let (code, broadcast_tx) = {
+                let mut room = room.write().unwrap();
+                room.touch();
+                (room.code.clone(), room.get_broadcast_tx())
             };
             let broadcast_rx = broadcast_tx.subscribe();
-            match voting_stage {
-                VotingStage::Vetoing => Ok((
-                    Box::new(VetoPage::new(
-                        room_code.to_string(),
-                        room.clone(),
-                        broadcast_tx,
-                    )),
-                    broadcast_rx,
-                )),
-                VotingStage::Ranking => Ok((
-                    Box::new(RankingPage::new(
-                        room_code.to_string(),
-                        room.clone(),
-                        broadcast_tx,
-                    )),
-                    broadcast_rx,
-                )),
-            }
+            Ok((
+                get_room_entry_page(code, room.clone(), broadcast_tx, participant),
+                broadcast_rx,
+            ))
         } else {
             Err(format!("Room \"{}\" not found", room_code))
         }
@@ -82,13 +123,19 @@ impl ServerState {
     pub fn get_room_results_page(
         &self,
         room_code: &str,
+        participant: ParticipantId,
     ) -> Result<(Box<dyn AppPage + Send + Sync>, BroadcastReceiver), String> {
-        if let Some(room) = self.rooms.get(room_code) {
-            let broadcast_rx = room.read().unwrap().get_broadcast_tx().subscribe();
+        if let Some(room) = self.get_room(room_code) {
+            let (code, broadcast_rx) = {
+                let mut room = room.write().unwrap();
+                room.touch();
+                (room.code.clone(), room.get_broadcast_tx().subscribe())
+            };
             Ok((
                 Box::new(ResultsPage {
-                    room_code: room_code.to_string(),
+                    room_code: code,
                     room_state: room.clone(),
+                    participant,
                 }),
                 broadcast_rx,
             ))
@@ -97,7 +144,37 @@ impl ServerState {
         }
     }
 
-    fn get_valid_room_code(&self) -> Result<String, ()> {
+    /// Removes every room that has been idle for at least `ttl`, letting anyone still in it know it closed
+    pub fn evict_idle_rooms(&mut self, ttl: Duration) -> Vec<String> {
+        let idle_room_codes: Vec<String> = self
+            .rooms
+            .iter()
+            .filter(|(_, room)| room.read().unwrap().is_idle_for(ttl))
+            .map(|(code, _)| code.clone())
+            .collect();
+
+        for code in idle_room_codes.iter() {
+            if let Some(room) = self.rooms.remove(code) {
+                room.write().unwrap().emit(RoomEvent::RoomClosed);
+            }
+            self.storage.delete_room(code);
+        }
+
+        idle_room_codes
+    }
+
+    /// Everyone who's in at least one room
+    pub fn participant_ids(&self) -> HashSet<ParticipantId> {
+        self.rooms
+            .values()
+            .flat_map(|room| {
+                let room = room.read().unwrap();
+                room.iter_participants().map(|p| p.id).collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    fn get_valid_room_code(&self) -> anyhow::Result<String> {
         const MAX_ROOM_CODE_ATTEMPTS: usize = 100;
         let mut attempts = 0;
         let mut room_code = Self::random_room_code();
@@ -107,7 +184,7 @@ impl ServerState {
         }
 
         if attempts >= MAX_ROOM_CODE_ATTEMPTS {
-            Err(())
+            bail!("Couldn't find a free room code")
         } else {
             Ok(room_code)
         }
@@ -117,3 +194,29 @@ impl ServerState {
         random_string::generate(4, ROOM_CODE_CHARSET)
     }
 }
+
+/// Periodically closes rooms that have been idle for at least `ttl`, and forgets sessions that have
+/// gone as long unused once their participant isn't in any room
+pub fn spawn_room_reaper(
+    state: ServerwideSharedState,
+    sessions: SharedSessions,
+    ttl: Duration,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval =
+            tokio::time::interval(ROOM_REAPER_INTERVAL.min(ttl).max(Duration::from_secs(1)));
+        loop {
+            interval.tick().await;
+            let evicted = state.write().unwrap().evict_idle_rooms(ttl);
+            if !evicted.is_empty() {
+                info!("Closed idle rooms: {}", evicted.join(", "));
+            }
+
+            let in_rooms = state.read().unwrap().participant_ids();
+            let forgotten = sessions.forget_idle(ttl, &in_rooms);
+            if forgotten > 0 {
+                info!("Forgot {} idle sessions", forgotten);
+            }
+        }
+    })
+}
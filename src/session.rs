@@ -0,0 +1,166 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use axum::{
+    http::{
+        header::{COOKIE, SET_COOKIE},
+        HeaderMap, HeaderValue, Request,
+    },
+    middleware::Next,
+    response::Response,
+};
+use uuid::Uuid;
+
+use crate::{
+    room_state::participant::ParticipantId,
+    storage::{writer::StorageWriter, RoomStorage},
+};
+
+const SESSION_COOKIE_NAME: &str = "whatdo_session";
+const SESSION_COOKIE_MAX_AGE_SECONDS: u64 = 60 * 60 * 24 * 365;
+/// How long a session is kept if its cookie never comes back, like one handed to a crawler
+const UNCONFIRMED_SESSION_TTL: Duration = Duration::from_secs(10 * 60);
+
+pub type SharedSessions = Arc<Sessions>;
+
+/// Which participant each session belongs to. Participant ids are shown to everyone in a room, so the
+/// session cookie holds a random token instead, and only the server knows whose it is.
+pub struct Sessions {
+    sessions: Mutex<HashMap<String, Session>>,
+    storage: StorageWriter,
+}
+
+struct Session {
+    participant: ParticipantId,
+    last_seen: Instant,
+    /// Whether the session has been saved to storage, which waits until its cookie comes back so
+    /// clients that drop cookies don't each leave one behind
+    saved: bool,
+}
+
+impl Sessions {
+    /// Picks up every session previously saved to `storage`
+    pub fn load(storage: &dyn RoomStorage, writer: StorageWriter) -> anyhow::Result<Self> {
+        let now = Instant::now();
+        let sessions = storage
+            .load_sessions()?
+            .into_iter()
+            .map(|(token, participant)| {
+                let session = Session {
+                    participant,
+                    last_seen: now,
+                    saved: true,
+                };
+                (token, session)
+            })
+            .collect();
+        Ok(Self {
+            sessions: Mutex::new(sessions),
+            storage: writer,
+        })
+    }
+
+    fn participant(&self, token: &str) -> Option<ParticipantId> {
+        let mut sessions = self.sessions.lock().unwrap();
+        let session = sessions.get_mut(token)?;
+        session.last_seen = Instant::now();
+        if !session.saved {
+            session.saved = true;
+            self.storage.save_session(token, session.participant);
+        }
+        Some(session.participant)
+    }
+
+    /// Starts a session for a new participant, returning its token. It's only saved once the cookie
+    /// comes back.
+    fn start(&self) -> (String, ParticipantId) {
+        let token = Uuid::new_v4().as_simple().to_string();
+        let participant = ParticipantId::new();
+        self.sessions.lock().unwrap().insert(
+            token.clone(),
+            Session {
+                participant,
+                last_seen: Instant::now(),
+                saved: false,
+            },
+        );
+        (token, participant)
+    }
+
+    /// Forgets sessions that haven't been used for at least `ttl`, unless their participant is still
+    /// in one of `in_rooms`. Sessions whose cookie never came back are forgotten sooner. Returns how
+    /// many were forgotten.
+    pub fn forget_idle(&self, ttl: Duration, in_rooms: &HashSet<ParticipantId>) -> usize {
+        let mut sessions = self.sessions.lock().unwrap();
+        let idle_tokens: Vec<String> = sessions
+            .iter()
+            .filter(|(_, session)| {
+                let session_ttl = if session.saved {
+                    ttl
+                } else {
+                    ttl.min(UNCONFIRMED_SESSION_TTL)
+                };
+                session.last_seen.elapsed() >= session_ttl
+                    && !in_rooms.contains(&session.participant)
+            })
+            .map(|(token, _)| token.clone())
+            .collect();
+        let forgotten = idle_tokens.len();
+
+        let saved_tokens: Vec<String> = idle_tokens
+            .into_iter()
+            .filter(|token| sessions.remove(token).is_some_and(|session| session.saved))
+            .collect();
+        if !saved_tokens.is_empty() {
+            self.storage.delete_sessions(saved_tokens);
+        }
+        forgotten
+    }
+}
+
+/// Makes the requester's `ParticipantId` available to handlers as an extension, starting a new session
+/// in a cookie if they don't have one yet
+pub async fn session_cookie<B>(mut request: Request<B>, next: Next<B>) -> Response {
+    let sessions = request
+        .extensions()
+        .get::<SharedSessions>()
+        .cloned()
+        .expect("Sessions are added before the session middleware runs");
+    let existing =
+        get_session_token(request.headers()).and_then(|token| sessions.participant(token));
+    let (participant, new_token) = match existing {
+        Some(participant) => (participant, None),
+        None => {
+            let (token, participant) = sessions.start();
+            (participant, Some(token))
+        }
+    };
+    request.extensions_mut().insert(participant);
+
+    let mut response = next.run(request).await;
+    if let Some(token) = new_token {
+        let cookie = format!(
+            "{}={}; Path=/; Max-Age={}; HttpOnly; SameSite=Lax",
+            SESSION_COOKIE_NAME, token, SESSION_COOKIE_MAX_AGE_SECONDS
+        );
+        response
+            .headers_mut()
+            .append(SET_COOKIE, HeaderValue::from_str(&cookie).unwrap());
+    }
+
+    response
+}
+
+fn get_session_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get_all(COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|cookie| cookie.trim().split_once('='))
+        .find(|(name, _)| *name == SESSION_COOKIE_NAME)
+        .map(|(_, value)| value)
+}
@@ -0,0 +1,21 @@
+use std::collections::HashMap;
+
+use crate::room_state::{participant::ParticipantId, snapshot::RoomSnapshot};
+
+pub mod noop;
+pub mod sqlite;
+pub mod writer;
+
+/// Where rooms and sessions are kept so they outlive the process. `ServerState` still serves rooms
+/// from memory, saving each one here through a `writer::StorageWriter` whenever it changes and
+/// loading them all back at startup.
+pub trait RoomStorage: Send + Sync {
+    fn load_rooms(&self) -> anyhow::Result<Vec<RoomSnapshot>>;
+    fn save_room(&self, room: &RoomSnapshot) -> anyhow::Result<()>;
+    fn delete_room(&self, code: &str) -> anyhow::Result<()>;
+    /// Every session token with the participant it belongs to, so people keep their place in rooms
+    /// across restarts
+    fn load_sessions(&self) -> anyhow::Result<HashMap<String, ParticipantId>>;
+    fn save_session(&self, token: &str, participant: ParticipantId) -> anyhow::Result<()>;
+    fn delete_sessions(&self, tokens: &[String]) -> anyhow::Result<()>;
+}
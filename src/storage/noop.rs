@@ -0,0 +1,34 @@
+use std::collections::HashMap;
+
+use crate::room_state::{participant::ParticipantId, snapshot::RoomSnapshot};
+
+use super::RoomStorage;
+
+/// Saves nothing, for when rooms only live as long as the process
+pub struct NoopStorage;
+
+impl RoomStorage for NoopStorage {
+    fn load_rooms(&self) -> anyhow::Result<Vec<RoomSnapshot>> {
+        Ok(Vec::new())
+    }
+
+    fn save_room(&self, _room: &RoomSnapshot) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn delete_room(&self, _code: &str) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn load_sessions(&self) -> anyhow::Result<HashMap<String, ParticipantId>> {
+        Ok(HashMap::new())
+    }
+
+    fn save_session(&self, _token: &str, _participant: ParticipantId) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn delete_sessions(&self, _tokens: &[String]) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
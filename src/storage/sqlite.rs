@@ -0,0 +1,477 @@
+use std::{collections::HashMap, path::Path, sync::Mutex};
+
+use rusqlite::{params, Connection, OptionalExtension};
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::room_state::{
+    option_details::OptionDetails,
+    participant::{Participant, ParticipantId},
+    snapshot::{BallotSnapshot, OptionSnapshot, RoomSnapshot},
+    veto_rules::Veto,
+    RoomSettings,
+};
+
+use super::RoomStorage;
+
+/// Schema changes, applied in order. `PRAGMA user_version` records how many have been applied.
+const MIGRATIONS: &[&str] = &["
+    CREATE TABLE rooms (
+        code TEXT PRIMARY KEY NOT NULL,
+        settings TEXT NOT NULL,
+        voting_stage TEXT NOT NULL,
+        host TEXT,
+        locked INTEGER NOT NULL DEFAULT 0
+    );
+    CREATE TABLE options (
+        room_code TEXT NOT NULL REFERENCES rooms(code) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        id TEXT NOT NULL,
+        text TEXT NOT NULL,
+        added_by TEXT,
+        description TEXT,
+        url TEXT,
+        image_url TEXT,
+        PRIMARY KEY (room_code, id)
+    );
+    CREATE TABLE vetoes (
+        room_code TEXT NOT NULL REFERENCES rooms(code) ON DELETE CASCADE,
+        option_id TEXT NOT NULL,
+        position INTEGER NOT NULL,
+        participant TEXT NOT NULL,
+        reason TEXT,
+        PRIMARY KEY (room_code, option_id, participant)
+    );
+    CREATE TABLE participants (
+        room_code TEXT NOT NULL REFERENCES rooms(code) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        id TEXT NOT NULL,
+        nickname TEXT NOT NULL,
+        ready INTEGER NOT NULL DEFAULT 0,
+        PRIMARY KEY (room_code, id)
+    );
+    CREATE TABLE kicked (
+        room_code TEXT NOT NULL REFERENCES rooms(code) ON DELETE CASCADE,
+        participant TEXT NOT NULL,
+        PRIMARY KEY (room_code, participant)
+    );
+    CREATE TABLE ballots (
+        room_code TEXT NOT NULL REFERENCES rooms(code) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        voter TEXT NOT NULL,
+        ballot TEXT NOT NULL,
+        PRIMARY KEY (room_code, position)
+    );
+    CREATE TABLE sessions (
+        token TEXT PRIMARY KEY NOT NULL,
+        participant TEXT NOT NULL
+    );
+"];
+
+/// Keeps rooms in a local SQLite database file
+pub struct SqliteStorage {
+    connection: Mutex<Connection>,
+}
+
+impl SqliteStorage {
+    pub fn open(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let mut connection = Connection::open(path)?;
+        connection.pragma_update(None, "foreign_keys", true)?;
+        migrate(&mut connection)?;
+        Ok(Self {
+            connection: Mutex::new(connection),
+        })
+    }
+}
+
+impl RoomStorage for SqliteStorage {
+    fn load_rooms(&self) -> anyhow::Result<Vec<RoomSnapshot>> {
+        let connection = self.connection.lock().unwrap();
+        let mut rooms_statement =
+            connection.prepare("SELECT code, settings, voting_stage, host, locked FROM rooms")?;
+        let rooms = rooms_statement
+            .query_map([], |row| {
+                Ok(RoomRow {
+                    code: row.get(0)?,
+                    settings: row.get(1)?,
+                    voting_stage: row.get(2)?,
+                    host: row.get(3)?,
+                    locked: row.get(4)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // One bad room shouldn't keep every other room from loading
+        Ok(rooms
+            .into_iter()
+            .filter_map(|room| {
+                let code = room.code.clone();
+                load_room(&connection, room)
+                    .map_err(|e| warn!("Skipping room {} that couldn't be loaded: {}", code, e))
+                    .ok()
+            })
+            .collect())
+    }
+
+    fn save_room(&self, room: &RoomSnapshot) -> anyhow::Result<()> {
+        let mut connection = self.connection.lock().unwrap();
+        let transaction = connection.transaction()?;
+        transaction.execute(
+            "INSERT INTO rooms (code, settings, voting_stage, host, locked) VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT (code) DO UPDATE SET settings = excluded.settings, voting_stage = excluded.voting_stage, host = excluded.host, locked = excluded.locked",
+            params![
+                room.code,
+                serde_json::to_string(&room.settings)?,
+                serde_json::to_string(&room.voting_stage)?,
+                room.host.map(|id| id.to_string()),
+                room.locked
+            ],
+        )?;
+
+        transaction.execute("DELETE FROM options WHERE room_code = ?1", [&room.code])?;
+        transaction.execute("DELETE FROM vetoes WHERE room_code = ?1", [&room.code])?;
+        for (position, option) in room.options.iter().enumerate() {
+            transaction.execute(
+                "INSERT INTO options (room_code, position, id, text, added_by, description, url, image_url) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                params![
+                    room.code,
+                    position as i64,
+                    option.id.to_string(),
+                    option.text,
+                    option.added_by.map(|id| id.to_string()),
+                    option.details.description(),
+                    option.details.url(),
+                    option.details.image_url()
+                ],
+            )?;
+            for (veto_position, veto) in option.vetoes.iter().enumerate() {
+                transaction.execute(
+                    "INSERT INTO vetoes (room_code, option_id, position, participant, reason) VALUES (?1, ?2, ?3, ?4, ?5)",
+                    params![
+                        room.code,
+                        option.id.to_string(),
+                        veto_position as i64,
+                        veto.by.to_string(),
+                        veto.reason()
+                    ],
+                )?;
+            }
+        }
+
+        transaction.execute(
+            "DELETE FROM participants WHERE room_code = ?1",
+            [&room.code],
+        )?;
+        for (position, participant) in room.participants.iter().enumerate() {
+            transaction.execute(
+                "INSERT INTO participants (room_code, position, id, nickname, ready) VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    room.code,
+                    position as i64,
+                    participant.id.to_string(),
+                    participant.nickname(),
+                    room.ready.contains(&participant.id)
+                ],
+            )?;
+        }
+
+        transaction.execute("DELETE FROM kicked WHERE room_code = ?1", [&room.code])?;
+        for participant in room.kicked.iter() {
+            transaction.execute(
+                "INSERT INTO kicked (room_code, participant) VALUES (?1, ?2)",
+                params![room.code, participant.to_string()],
+            )?;
+        }
+
+        transaction.execute("DELETE FROM ballots WHERE room_code = ?1", [&room.code])?;
+        for (position, ballot) in room.ballots.iter().enumerate() {
+            transaction.execute(
+                "INSERT INTO ballots (room_code, position, voter, ballot) VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    room.code,
+                    position as i64,
+                    ballot.voter.to_string(),
+                    serde_json::to_string(&ballot.ballot)?
+                ],
+            )?;
+        }
+
+        transaction.commit()?;
+        Ok(())
+    }
+
+    fn delete_room(&self, code: &str) -> anyhow::Result<()> {
+        self.connection
+            .lock()
+            .unwrap()
+            .execute("DELETE FROM rooms WHERE code = ?1", [code])?;
+        Ok(())
+    }
+
+    fn load_sessions(&self) -> anyhow::Result<HashMap<String, ParticipantId>> {
+        let connection = self.connection.lock().unwrap();
+        let mut statement = connection.prepare("SELECT token, participant FROM sessions")?;
+        let sessions = statement
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })?
+            .map(|row| {
+                let (token, participant) = row?;
+                Ok((token, participant.parse()?))
+            })
+            .collect::<anyhow::Result<HashMap<_, _>>>()?;
+        Ok(sessions)
+    }
+
+    fn save_session(&self, token: &str, participant: ParticipantId) -> anyhow::Result<()> {
+        self.connection.lock().unwrap().execute(
+            "INSERT OR REPLACE INTO sessions (token, participant) VALUES (?1, ?2)",
+            params![token, participant.to_string()],
+        )?;
+        Ok(())
+    }
+
+    fn delete_sessions(&self, tokens: &[String]) -> anyhow::Result<()> {
+        let mut connection = self.connection.lock().unwrap();
+        let transaction = connection.transaction()?;
+        for token in tokens {
+            transaction.execute("DELETE FROM sessions WHERE token = ?1", [token])?;
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+}
+
+struct RoomRow {
+    code: String,
+    settings: String,
+    voting_stage: String,
+    host: Option<String>,
+    locked: bool,
+}
+
+fn load_room(connection: &Connection, room: RoomRow) -> anyhow::Result<RoomSnapshot> {
+    let settings: RoomSettings = serde_json::from_str(&room.settings)?;
+    let veto_threshold = usize::from(settings.veto_threshold.max(1));
+
+    let mut vetoes_statement = connection.prepare_cached(
+        "SELECT participant, reason FROM vetoes WHERE room_code = ?1 AND option_id = ?2 ORDER BY position",
+    )?;
+    let options = connection
+        .prepare_cached(
+            "SELECT id, text, added_by, description, url, image_url FROM options WHERE room_code = ?1 ORDER BY position",
+        )?
+        .query_map([&room.code], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, Option<String>>(2)?,
+                row.get::<_, Option<String>>(3)?,
+                row.get::<_, Option<String>>(4)?,
+                row.get::<_, Option<String>>(5)?,
+            ))
+        })?
+        .map(|row| {
+            let (id, text, added_by, description, url, image_url) = row?;
+            let vetoes = vetoes_statement
+                .query_map([&room.code, &id], |row| {
+                    Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?))
+                })?
+                .map(|row| {
+                    let (participant, reason) = row?;
+                    Veto::new(participant.parse()?, reason.as_deref().unwrap_or(""))
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            Ok(OptionSnapshot {
+                id: Uuid::parse_str(&id)?,
+                text,
+                details: OptionDetails::new(
+                    description.as_deref().unwrap_or(""),
+                    url.as_deref().unwrap_or(""),
+                    image_url.as_deref().unwrap_or(""),
+                )?,
+                vetoed: vetoes.len() >= veto_threshold,
+                added_by: added_by.map(|id| id.parse()).transpose()?,
+                vetoes,
+            })
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let participants = connection
+        .prepare_cached(
+            "SELECT id, nickname, ready FROM participants WHERE room_code = ?1 ORDER BY position",
+        )?
+        .query_map([&room.code], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, bool>(2)?,
+            ))
+        })?
+        .map(|row| {
+            let (id, nickname, ready) = row?;
+            Ok((Participant::new(id.parse()?, &nickname)?, ready))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let ready = participants
+        .iter()
+        .filter(|(_, ready)| *ready)
+        .map(|(participant, _)| participant.id)
+        .collect();
+    let participants = participants
+        .into_iter()
+        .map(|(participant, _)| participant)
+        .collect();
+
+    let ballots = connection
+        .prepare_cached("SELECT voter, ballot FROM ballots WHERE room_code = ?1 ORDER BY position")?
+        .query_map([&room.code], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?
+        .map(|row| {
+            let (voter, ballot) = row?;
+            Ok(BallotSnapshot {
+                voter: voter.parse()?,
+                ballot: serde_json::from_str(&ballot)?,
+            })
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let kicked = connection
+        .prepare_cached("SELECT participant FROM kicked WHERE room_code = ?1")?
+        .query_map([&room.code], |row| row.get::<_, String>(0))?
+        .map(|row| Ok(row?.parse()?))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    Ok(RoomSnapshot {
+        code: room.code,
+        settings,
+        voting_stage: serde_json::from_str(&room.voting_stage)?,
+        options,
+        participants,
+        host: room.host.map(|id| id.parse()).transpose()?,
+        locked: room.locked,
+        kicked,
+        ready,
+        ballots,
+    })
+}
+
+fn migrate(connection: &mut Connection) -> anyhow::Result<()> {
+    let applied: i64 = connection
+        .query_row("PRAGMA user_version", [], |row| row.get(0))
+        .optional()?
+        .unwrap_or(0);
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(applied as usize) {
+        let transaction = connection.transaction()?;
+        transaction.execute_batch(migration)?;
+        transaction.pragma_update(None, "user_version", (index + 1) as i64)?;
+        transaction.commit()?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::room_state::{
+        ballot::Ballot,
+        test_support::{join, room},
+    };
+
+    /// A room with a bit of everything to save: option details, vetoes with and without reasons,
+    /// a struck option, a kicked participant, a ready mark and ballots
+    fn busy_room() -> RoomSnapshot {
+        let settings = RoomSettings {
+            veto_limit: Some(2),
+            veto_threshold: 2,
+            ..RoomSettings::default()
+        };
+        let (mut room, host, ids) = room(&["Bowling", "Picnic", "Cinema"], settings);
+        let ann = join(&mut room, "Ann");
+        let bob = join(&mut room, "Bob");
+        room.add_option(
+            host,
+            "Karaoke".to_string(),
+            OptionDetails::new("Bring earplugs", "https://example.com", "").unwrap(),
+        )
+        .unwrap();
+        room.veto(ann, ids[2], "Too far").unwrap();
+        room.veto(host, ids[2], "").unwrap();
+        room.veto(ann, ids[1], "").unwrap();
+        room.kick(host, bob).unwrap();
+        room.set_locked(host, true).unwrap();
+        room.mark_ready(ann, true).unwrap();
+        room.finish_vetoing(host).unwrap();
+        room.contribute_ballot(host, Ballot::Ranked(vec![ids[0], ids[1]]))
+            .unwrap();
+        room.contribute_ballot(ann, Ballot::Ranked(vec![ids[1]]))
+            .unwrap();
+        room.snapshot()
+    }
+
+    fn open() -> SqliteStorage {
+        SqliteStorage::open(":memory:").unwrap()
+    }
+
+    #[test]
+    fn rooms_load_as_they_were_saved() {
+        let storage = open();
+        let room = busy_room();
+
+        storage.save_room(&room).unwrap();
+        let loaded = storage.load_rooms().unwrap();
+
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(
+            serde_json::to_value(&loaded[0]).unwrap(),
+            serde_json::to_value(&room).unwrap()
+        );
+    }
+
+    #[test]
+    fn deleted_rooms_stay_deleted() {
+        let storage = open();
+        storage.save_room(&busy_room()).unwrap();
+
+        storage.delete_room("test").unwrap();
+
+        assert!(storage.load_rooms().unwrap().is_empty());
+    }
+
+    #[test]
+    fn rooms_that_fail_to_load_are_skipped() {
+        let storage = open();
+        storage.save_room(&busy_room()).unwrap();
+        storage
+            .connection
+            .lock()
+            .unwrap()
+            .execute(
+                "INSERT INTO rooms (code, settings, voting_stage) VALUES ('bad', 'not json', '\"Vetoing\"')",
+                [],
+            )
+            .unwrap();
+
+        let loaded = storage.load_rooms().unwrap();
+
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].code, "test");
+    }
+
+    #[test]
+    fn sessions_load_until_deleted() {
+        let storage = open();
+        let participant = ParticipantId::new();
+
+        storage.save_session("token", participant).unwrap();
+        assert_eq!(
+            storage.load_sessions().unwrap(),
+            HashMap::from([("token".to_string(), participant)])
+        );
+
+        storage.delete_sessions(&["token".to_string()]).unwrap();
+        assert!(storage.load_sessions().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,123 @@
+use std::{
+    collections::HashMap,
+    sync::{mpsc, Arc},
+    thread,
+};
+
+use tracing::error;
+
+use crate::room_state::{participant::ParticipantId, snapshot::RoomSnapshot};
+
+use super::RoomStorage;
+
+enum Write {
+    SaveRoom(RoomSnapshot),
+    DeleteRoom(String),
+    SaveSession(String, ParticipantId),
+    DeleteSessions(Vec<String>),
+}
+
+impl Write {
+    /// The room the write replaces, if it's a room write
+    fn room_code(&self) -> Option<&str> {
+        match self {
+            Write::SaveRoom(room) => Some(&room.code),
+            Write::DeleteRoom(code) => Some(code),
+            Write::SaveSession(..) | Write::DeleteSessions(_) => None,
+        }
+    }
+}
+
+/// Hands writes to a thread of their own, so nothing holding a room's lock has to wait on the disk.
+/// Writes are applied in the order they're queued, and when several are waiting for the same room
+/// only the last one is applied.
+#[derive(Clone)]
+pub struct StorageWriter {
+    /// `None` if writes are dropped rather than saved
+    writes_tx: Option<mpsc::Sender<Write>>,
+}
+
+impl StorageWriter {
+    pub fn spawn(storage: Arc<dyn RoomStorage>) -> Self {
+        let (writes_tx, writes_rx) = mpsc::channel();
+        thread::Builder::new()
+            .name("room-storage".to_string())
+            .spawn(move || write_all(storage, writes_rx))
+            .expect("Failed to start the room storage thread");
+        Self {
+            writes_tx: Some(writes_tx),
+        }
+    }
+
+    /// A writer that drops every write without starting a thread, for when nothing is saved
+    pub fn disabled() -> Self {
+        Self { writes_tx: None }
+    }
+
+    pub fn save_room(&self, room: RoomSnapshot) {
+        self.queue(Write::SaveRoom(room));
+    }
+
+    pub fn delete_room(&self, code: &str) {
+        self.queue(Write::DeleteRoom(code.to_string()));
+    }
+
+    pub fn save_session(&self, token: &str, participant: ParticipantId) {
+        self.queue(Write::SaveSession(token.to_string(), participant));
+    }
+
+    pub fn delete_sessions(&self, tokens: Vec<String>) {
+        self.queue(Write::DeleteSessions(tokens));
+    }
+
+    fn queue(&self, write: Write) {
+        let Some(writes_tx) = &self.writes_tx else {
+            return;
+        };
+        if writes_tx.send(write).is_err() {
+            error!("The room storage thread has stopped, so changes aren't being saved");
+        }
+    }
+}
+
+fn write_all(storage: Arc<dyn RoomStorage>, writes_rx: mpsc::Receiver<Write>) {
+    while let Ok(write) = writes_rx.recv() {
+        let pending: Vec<Write> = std::iter::once(write).chain(writes_rx.try_iter()).collect();
+        let last_for_room: HashMap<&str, usize> = pending
+            .iter()
+            .enumerate()
+            .filter_map(|(index, write)| Some((write.room_code()?, index)))
+            .collect();
+
+        for (index, write) in pending.iter().enumerate() {
+            if write
+                .room_code()
+                .is_some_and(|code| last_for_room[code] != index)
+            {
+                continue;
+            }
+            match write {
+                Write::SaveRoom(room) => {
+                    if let Err(e) = storage.save_room(room) {
+                        error!("Failed to save room {}: {}", room.code, e);
+                    }
+                }
+                Write::DeleteRoom(code) => {
+                    if let Err(e) = storage.delete_room(code) {
+                        error!("Failed to delete room {}: {}", code, e);
+                    }
+                }
+                Write::SaveSession(token, participant) => {
+                    if let Err(e) = storage.save_session(token, *participant) {
+                        error!("Failed to save a session: {}", e);
+                    }
+                }
+                Write::DeleteSessions(tokens) => {
+                    if let Err(e) = storage.delete_sessions(tokens) {
+                        error!("Failed to delete idle sessions: {}", e);
+                    }
+                }
+            }
+        }
+    }
+}